@@ -0,0 +1,434 @@
+//! Turning the tree back into source. `Display` writes a node in canonical
+//! form: four-space indentation, one statement per line and only the
+//! parentheses the parser needs. `SourceWriter` does the same for a whole
+//! program and can also put back the comments of the source it was parsed
+//! from, which is what `racoon fmt` relies on.
+
+use std::fmt;
+
+use crate::{
+    lexer::{escape, quote},
+    tkn::Span,
+};
+
+use super::{Expression, Infix, Literal, Precedence, Prefix, Statement};
+
+const INDENT: &str = "    ";
+/// Longest statement kept on the same line as its braces, as in `{ x * 2 }`.
+const MAX_INLINE_BLOCK: usize = 60;
+
+#[derive(Default)]
+pub struct SourceWriter<'a> {
+    out: String,
+    depth: usize,
+    /// The source being reformatted, when comments are to be kept.
+    source: Option<&'a str>,
+    /// Spans of the comments in `source`, in order, and the first one not yet written.
+    comments: Vec<Span>,
+    next_comment: usize,
+    /// Where in `source` the last statement or comment written ends.
+    last_end: usize,
+}
+
+impl<'a> SourceWriter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A writer that places each of `comments`, the spans of the comments in
+    /// `source` including doc comments, next to the statement it was written by.
+    /// Single blank lines between statements are kept as well.
+    pub fn with_comments(source: &'a str, mut comments: Vec<Span>) -> Self {
+        comments.sort_by_key(|span| span.start);
+        Self {
+            source: Some(source),
+            comments,
+            ..Self::default()
+        }
+    }
+
+    /// Writes `program` as a file, ending with a newline unless it is empty.
+    pub fn program(mut self, program: &[Statement]) -> String {
+        self.statements(program, usize::MAX);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    /// Writes each statement on its own line, followed by the comments that
+    /// come before `end`.
+    fn statements(&mut self, statements: &[Statement], end: usize) {
+        // `if` statements don't need a `;`, unless the next statement would
+        // otherwise be read as an index, call or operand of the `if`.
+        let mut open_if = None;
+        for statement in statements {
+            self.comments_before(statement.span().start);
+            self.start_line(statement.span().start);
+            if let (None, Statement::Let { doc: Some(doc), .. }) = (self.source, statement) {
+                for line in doc.lines() {
+                    self.doc_line(line);
+                }
+            }
+
+            let start = self.out.len();
+            self.statement(statement);
+            if let Some(position) = open_if.take() {
+                if self.out[start..].starts_with(['(', '[', '-', '+']) {
+                    self.out.insert(position, ';');
+                }
+            }
+            if let Statement::Expression {
+                expression: Expression::If { .. },
+                ..
+            } = statement
+            {
+                open_if = Some(self.out.len());
+            }
+
+            self.last_end = statement.span().end;
+            self.trailing_comment();
+            self.comments_before(statement.span().end);
+        }
+        self.comments_before(end);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { name, value, .. } => {
+                self.out.push_str(&format!("let {} = ", name));
+                self.expression(value);
+                self.out.push(';');
+            }
+            Statement::Return { return_value, .. } => {
+                self.out.push_str("return ");
+                self.expression(return_value);
+                self.out.push(';');
+            }
+            Statement::Expression { expression, .. } => {
+                self.expression(expression);
+                if !matches!(expression, Expression::If { .. }) {
+                    self.out.push(';');
+                }
+            }
+            Statement::While {
+                condition,
+                body,
+                span,
+            } => {
+                self.out.push_str("while ");
+                self.expression(condition);
+                self.out.push(' ');
+                self.block(body, span.end);
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+                span,
+            } => {
+                self.out.push_str(&format!("for {} in ", variable));
+                self.expression(iterable);
+                self.out.push(' ');
+                self.block(body, span.end);
+            }
+            Statement::Assign {
+                target,
+                operator,
+                value,
+                ..
+            } => {
+                self.expression(target);
+                match operator {
+                    Some(operator) => self.out.push_str(&format!(" {}= ", operator)),
+                    None => self.out.push_str(" = "),
+                }
+                self.expression(value);
+                self.out.push(';');
+            }
+            Statement::Break(_) => self.out.push_str("break;"),
+            Statement::Continue(_) => self.out.push_str("continue;"),
+        }
+    }
+
+    /// Writes a block whose closing brace comes before `end` in the source.
+    /// A block holding one short expression stays on one line.
+    fn block(&mut self, block: &[Statement], end: usize) {
+        let has_comments = self.next_comment_before(end).is_some();
+        if block.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+        if let (false, [statement]) = (has_comments, block) {
+            let line = match statement {
+                Statement::Expression { expression, .. } => expression.to_string(),
+                Statement::Break(_) => String::from("break"),
+                Statement::Continue(_) => String::from("continue"),
+                _ => String::new(),
+            };
+            if !line.is_empty() && line.len() <= MAX_INLINE_BLOCK && !line.contains('\n') {
+                self.out.push_str(&format!("{{ {} }}", line));
+                return;
+            }
+        }
+
+        self.out.push('{');
+        self.depth += 1;
+        self.statements(block, end);
+        self.depth -= 1;
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.depth));
+        self.out.push('}');
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.out.push_str(&ident.literal),
+            Expression::Literal(literal, _) => self.literal(literal),
+            Expression::Prefix(prefix, right, _) => {
+                self.out.push_str(&prefix.to_string());
+                // `--x` and `+-x` read badly, so the inner sign gets parentheses.
+                let is_sign = |prefix: &Prefix| matches!(prefix, Prefix::Minus | Prefix::Plus);
+                let parenthesize = match &**right {
+                    Expression::Prefix(inner, ..) => is_sign(prefix) && is_sign(inner),
+                    right => precedence(right) < Precedence::Prefix,
+                };
+                self.operand(right, parenthesize);
+            }
+            Expression::Infix(infix, left, right, _) => {
+                let precedence_of = infix.precedence();
+                // `**` groups to the right, every other operator to the left.
+                let right_associative = *infix == Infix::Power;
+                let left_parens = precedence(left) < precedence_of
+                    || (right_associative && precedence(left) == precedence_of);
+                let right_parens = match &**right {
+                    // An operand may always start with a prefix operator.
+                    Expression::Prefix(..) => false,
+                    right => {
+                        precedence(right) < precedence_of
+                            || (!right_associative && precedence(right) == precedence_of)
+                    }
+                };
+                self.operand(left, left_parens);
+                self.out.push_str(&format!(" {} ", infix));
+                self.operand(right, right_parens);
+            }
+            Expression::Index(left, index, _) => {
+                self.operand(left, precedence(left) < Precedence::Call);
+                self.out.push('[');
+                self.expression(index);
+                self.out.push(']');
+            }
+            Expression::Call { func, args, .. } => {
+                self.operand(func, precedence(func) < Precedence::Call);
+                self.out.push('(');
+                self.list(args.as_deref().unwrap_or_default());
+                self.out.push(')');
+            }
+            Expression::Interpolation(parts, _) => {
+                self.out.push('"');
+                for part in parts {
+                    match part {
+                        Expression::Literal(Literal::String { value: text, .. }, _) => {
+                            self.out.push_str(&escape(text))
+                        }
+                        part => {
+                            self.out.push_str("${");
+                            self.expression(part);
+                            self.out.push('}');
+                        }
+                    }
+                }
+                self.out.push('"');
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+                else_span,
+                span,
+            } => {
+                self.out.push_str("if ");
+                self.expression(condition);
+                self.out.push(' ');
+                let consequence_end = else_span.map_or(span.end, |else_span| else_span.start);
+                self.block(consequence, consequence_end);
+                if let Some(alternative) = alternative {
+                    self.out.push_str(" else ");
+                    self.block(alternative, span.end);
+                }
+            }
+            Expression::Function { params, body, span } => {
+                let params = params.iter().map(|param| param.literal.as_str());
+                self.out
+                    .push_str(&format!("func({}) ", params.collect::<Vec<_>>().join(", ")));
+                self.block(body, span.end);
+            }
+        }
+    }
+
+    fn operand(&mut self, operand: &Expression, parenthesize: bool) {
+        if parenthesize {
+            self.out.push('(');
+            self.expression(operand);
+            self.out.push(')');
+        } else {
+            self.expression(operand);
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            // Numbers are written as they were, keeping any base prefix and `_`.
+            Literal::Int { token, value } if token.literal.is_empty() => {
+                self.out.push_str(&value.to_string())
+            }
+            Literal::BigInt { token, value } if token.literal.is_empty() => {
+                self.out.push_str(&value.to_string())
+            }
+            Literal::Float { token, value } if token.literal.is_empty() => {
+                self.out.push_str(&format!("{:?}", value))
+            }
+            Literal::String { token, value } if token.literal.is_empty() => {
+                self.out.push_str(&quote(value))
+            }
+            Literal::Int { token, .. }
+            | Literal::BigInt { token, .. }
+            | Literal::Float { token, .. }
+            | Literal::String { token, .. } => self.out.push_str(&token.literal),
+            Literal::Bool(b) => self.out.push_str(&b.to_string()),
+            Literal::Array(items) => {
+                self.out.push('[');
+                self.list(items);
+                self.out.push(']');
+            }
+            Literal::Hash(pairs) => {
+                self.out.push('{');
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(key);
+                    self.out.push_str(": ");
+                    self.expression(value);
+                }
+                self.out.push('}');
+            }
+        }
+    }
+
+    fn list(&mut self, items: &[Expression]) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expression(item);
+        }
+    }
+
+    /// Starts a new line at the current indentation for something at `start`
+    /// in the source, leaving a blank line before it if the source has one.
+    fn start_line(&mut self, start: usize) {
+        if self.out.is_empty() {
+            return;
+        }
+        let gap = self
+            .source
+            .and_then(|source| source.get(self.last_end..start));
+        if gap.is_some_and(|gap| gap.trim().is_empty() && gap.matches('\n').count() > 1)
+            && !self.out.ends_with('{')
+        {
+            self.out.push('\n');
+        }
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+
+    fn doc_line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.out.push_str("///");
+        } else {
+            self.out.push_str(&format!("/// {}", line));
+        }
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+
+    fn next_comment_before(&self, end: usize) -> Option<Span> {
+        self.comments
+            .get(self.next_comment)
+            .filter(|comment| comment.start < end)
+            .copied()
+    }
+
+    /// Writes the comments that start before `end` on lines of their own.
+    /// Comments inside an expression end up before the next statement.
+    fn comments_before(&mut self, end: usize) {
+        while let Some(comment) = self.next_comment_before(end) {
+            self.start_line(comment.start);
+            self.write_comment(comment);
+        }
+    }
+
+    /// Writes the next comment after the statement just written if the source
+    /// has it on the same line or inside the statement, where it has no line
+    /// of its own to go on.
+    fn trailing_comment(&mut self) {
+        let (Some(source), Some(&comment)) = (self.source, self.comments.get(self.next_comment))
+        else {
+            return;
+        };
+        let same_line = source
+            .get(self.last_end..comment.start)
+            .is_some_and(|gap| gap.trim().is_empty() && !gap.contains('\n'));
+        if comment.start < self.last_end || same_line {
+            self.out.push(' ');
+            self.write_comment(comment);
+        }
+    }
+
+    fn write_comment(&mut self, comment: Span) {
+        if let Some(text) = self
+            .source
+            .and_then(|source| source.get(comment.start..comment.end))
+        {
+            self.out.push_str(text.trim_end());
+        }
+        self.last_end = self.last_end.max(comment.end);
+        self.next_comment += 1;
+    }
+}
+
+/// How tightly an expression holds together as an operand. Anything that isn't
+/// an operator application can be used anywhere without parentheses.
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::Prefix(..) => Precedence::Prefix,
+        Expression::Infix(infix, ..) => infix.precedence(),
+        _ => Precedence::Index,
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut writer = SourceWriter::new();
+        writer.expression(self);
+        f.write_str(&writer.out)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut writer = SourceWriter::new();
+        writer.statements(std::slice::from_ref(self), usize::MAX);
+        f.write_str(&writer.out)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut writer = SourceWriter::new();
+        writer.literal(self);
+        f.write_str(&writer.out)
+    }
+}
@@ -0,0 +1,234 @@
+//! Rewriting traversal of the tree. A `Folder` takes each node by value and
+//! returns its replacement. The `fold_*` defaults call the matching `walk_*`
+//! function, which rebuilds the node from its folded children, so an override
+//! only handles the nodes it rewrites and calls `walk_*` for the rest.
+
+use crate::tkn::Span;
+
+use super::{BlockOfStatements, Expression, Identifier, Literal, Statement};
+
+pub trait Folder {
+    /// Folds each statement in turn. Overriding this is the way to drop
+    /// statements or add new ones.
+    fn fold_block(&mut self, block: BlockOfStatements) -> BlockOfStatements {
+        walk_block(self, block)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement(self, statement)
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        walk_expression(self, expression)
+    }
+
+    fn fold_literal(&mut self, literal: Literal, _span: Span) -> Literal {
+        walk_literal(self, literal)
+    }
+
+    /// Called for names being read as well as names being bound: `let` and
+    /// `for` variables and function parameters.
+    fn fold_identifier(&mut self, ident: Identifier) -> Identifier {
+        ident
+    }
+}
+
+pub fn walk_block<F: Folder + ?Sized>(
+    folder: &mut F,
+    block: BlockOfStatements,
+) -> BlockOfStatements {
+    block
+        .into_iter()
+        .map(|statement| folder.fold_statement(statement))
+        .collect()
+}
+
+pub fn walk_statement<F: Folder + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Let {
+            name,
+            value,
+            doc,
+            span,
+        } => Statement::Let {
+            name: folder.fold_identifier(name),
+            value: folder.fold_expression(value),
+            doc,
+            span,
+        },
+        Statement::Return { return_value, span } => Statement::Return {
+            return_value: folder.fold_expression(return_value),
+            span,
+        },
+        Statement::Expression { expression, span } => Statement::Expression {
+            expression: folder.fold_expression(expression),
+            span,
+        },
+        Statement::While {
+            condition,
+            body,
+            span,
+        } => Statement::While {
+            condition: folder.fold_expression(condition),
+            body: folder.fold_block(body),
+            span,
+        },
+        Statement::For {
+            variable,
+            iterable,
+            body,
+            span,
+        } => Statement::For {
+            variable: folder.fold_identifier(variable),
+            iterable: folder.fold_expression(iterable),
+            body: folder.fold_block(body),
+            span,
+        },
+        Statement::Assign {
+            target,
+            operator,
+            value,
+            span,
+        } => Statement::Assign {
+            target: folder.fold_expression(target),
+            operator,
+            value: folder.fold_expression(value),
+            span,
+        },
+        Statement::Break(span) => Statement::Break(span),
+        Statement::Continue(span) => Statement::Continue(span),
+    }
+}
+
+pub fn walk_expression<F: Folder + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Identifier(ident) => Expression::Identifier(folder.fold_identifier(ident)),
+        Expression::Literal(literal, span) => {
+            Expression::Literal(folder.fold_literal(literal, span), span)
+        }
+        Expression::Prefix(prefix, right, span) => {
+            Expression::Prefix(prefix, fold_boxed(folder, right), span)
+        }
+        Expression::Infix(infix, left, right, span) => {
+            let left = fold_boxed(folder, left);
+            Expression::Infix(infix, left, fold_boxed(folder, right), span)
+        }
+        Expression::Index(left, index, span) => {
+            let left = fold_boxed(folder, left);
+            Expression::Index(left, fold_boxed(folder, index), span)
+        }
+        Expression::Interpolation(parts, span) => Expression::Interpolation(
+            parts
+                .into_iter()
+                .map(|part| folder.fold_expression(part))
+                .collect(),
+            span,
+        ),
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+            else_span,
+            span,
+        } => Expression::If {
+            condition: fold_boxed(folder, condition),
+            consequence: folder.fold_block(consequence),
+            alternative: alternative.map(|alternative| folder.fold_block(alternative)),
+            else_span,
+            span,
+        },
+        Expression::Function { params, body, span } => Expression::Function {
+            params: params
+                .into_iter()
+                .map(|param| folder.fold_identifier(param))
+                .collect(),
+            body: folder.fold_block(body),
+            span,
+        },
+        Expression::Call { func, args, span } => Expression::Call {
+            func: fold_boxed(folder, func),
+            args: args.map(|args| {
+                args.into_iter()
+                    .map(|arg| folder.fold_expression(arg))
+                    .collect()
+            }),
+            span,
+        },
+    }
+}
+
+/// Folds a boxed child in place, reusing its allocation.
+fn fold_boxed<F: Folder + ?Sized>(
+    folder: &mut F,
+    mut expression: Box<Expression>,
+) -> Box<Expression> {
+    *expression = folder.fold_expression(*expression);
+    expression
+}
+
+pub fn walk_literal<F: Folder + ?Sized>(folder: &mut F, literal: Literal) -> Literal {
+    match literal {
+        Literal::Array(items) => Literal::Array(
+            items
+                .into_iter()
+                .map(|item| folder.fold_expression(item))
+                .collect(),
+        ),
+        Literal::Hash(pairs) => Literal::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (folder.fold_expression(key), folder.fold_expression(value)))
+                .collect(),
+        ),
+        literal => literal,
+    }
+}
+
+#[cfg(test)]
+mod fold_test {
+    use super::*;
+    use crate::{abstract_tree::SourceWriter, lexer::Lexer, parser::Parser};
+
+    /// Renames `old` to `new` and drops `break` statements.
+    struct Rename {
+        old: &'static str,
+        new: &'static str,
+    }
+
+    impl Folder for Rename {
+        fn fold_block(&mut self, block: BlockOfStatements) -> BlockOfStatements {
+            let block = walk_block(self, block);
+            block
+                .into_iter()
+                .filter(|statement| !matches!(statement, Statement::Break(_)))
+                .collect()
+        }
+
+        fn fold_identifier(&mut self, mut ident: Identifier) -> Identifier {
+            if ident.literal == self.old {
+                ident.literal = self.new.to_string();
+            }
+            ident
+        }
+    }
+
+    #[test]
+    fn test_folder() {
+        let input = "let x = func(x) { x[x] + f(x) };
+for x in [x, {x: \"${x}\"}] { while x { break; x += -x } }";
+        let program = Parser::new(Lexer::new(input)).parse_program();
+
+        let mut rename = Rename { old: "x", new: "y" };
+        let program = rename.fold_block(program);
+        assert_eq!(
+            SourceWriter::new().program(&program),
+            "let y = func(y) { y[y] + f(y) };
+for y in [y, {y: \"${y}\"}] {
+    while y {
+        y += -y;
+    }
+}
+"
+        );
+    }
+}
@@ -1,7 +1,21 @@
-use crate::tkn::Token;
+use std::fmt;
+
+use crate::tkn::{Span, Token};
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Identifier {
     pub literal: String,
     pub token: Token,
 }
+
+impl Identifier {
+    pub fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.literal)
+    }
+}
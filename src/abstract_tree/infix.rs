@@ -1,5 +1,7 @@
 use std::fmt;
 
+use super::Precedence;
+
 #[derive(PartialEq, Clone, Debug)]
 
 pub enum Infix {
@@ -7,6 +9,15 @@ pub enum Infix {
     Minus,
     Divide,
     Multiply,
+    Modulo,
+    Power,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Equal,
     NotEqual,
     GreaterThanEqual,
@@ -22,6 +33,15 @@ impl fmt::Display for Infix {
             Infix::Minus => write!(f, "-"),
             Infix::Divide => write!(f, "/"),
             Infix::Multiply => write!(f, "*"),
+            Infix::Modulo => write!(f, "%"),
+            Infix::Power => write!(f, "**"),
+            Infix::And => write!(f, "&&"),
+            Infix::Or => write!(f, "||"),
+            Infix::BitAnd => write!(f, "&"),
+            Infix::BitOr => write!(f, "|"),
+            Infix::BitXor => write!(f, "^"),
+            Infix::ShiftLeft => write!(f, "<<"),
+            Infix::ShiftRight => write!(f, ">>"),
             Infix::Equal => write!(f, "=="),
             Infix::NotEqual => write!(f, "!="),
             Infix::GreaterThanEqual => write!(f, ">="),
@@ -31,3 +51,26 @@ impl fmt::Display for Infix {
         }
     }
 }
+
+impl Infix {
+    /// How tightly the operator binds, matching the precedence the parser
+    /// gives its token.
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Infix::Or => Precedence::LogicalOr,
+            Infix::And => Precedence::LogicalAnd,
+            Infix::Equal | Infix::NotEqual => Precedence::Equals,
+            Infix::LessThan
+            | Infix::LessThanEqual
+            | Infix::GreaterThan
+            | Infix::GreaterThanEqual => Precedence::LessGreater,
+            Infix::BitOr => Precedence::BitOr,
+            Infix::BitXor => Precedence::BitXor,
+            Infix::BitAnd => Precedence::BitAnd,
+            Infix::ShiftLeft | Infix::ShiftRight => Precedence::Shift,
+            Infix::Plus | Infix::Minus => Precedence::Sum,
+            Infix::Multiply | Infix::Divide | Infix::Modulo => Precedence::Product,
+            Infix::Power => Precedence::Power,
+        }
+    }
+}
@@ -1,11 +1,28 @@
-use crate::tkn::Token;
+use crate::{evaluator::BigInt, tkn::Token};
 
 use super::Expression;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum Literal {
-    Int { token: Token, value: i64 },
-    String(String),
+    Int {
+        token: Token,
+        value: i64,
+    },
+    /// An integer literal too large for an `i64`.
+    BigInt {
+        token: Token,
+        value: BigInt,
+    },
+    Float {
+        token: Token,
+        value: f64,
+    },
+    /// The token's literal is the string as written, quotes and all, and is
+    /// empty for the text between the `${}` of an interpolation.
+    String {
+        token: Token,
+        value: String,
+    },
     Bool(bool),
     Array(Vec<Expression>),
     Hash(Vec<(Expression, Expression)>),
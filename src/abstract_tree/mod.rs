@@ -1,46 +1,124 @@
+mod display;
+pub mod fold;
 mod ident;
 mod infix;
 mod literal;
 mod precedence;
 mod prefix;
+pub mod visit;
 
 use std::fmt::Debug;
 
+pub use display::SourceWriter;
 pub use ident::Identifier;
 pub use infix::Infix;
 pub use literal::Literal;
 pub use precedence::Precedence;
 pub use prefix::Prefix;
 
+use crate::tkn::Span;
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum Expression {
     Identifier(Identifier),
-    Literal(Literal),
-    Prefix(Prefix, Box<Expression>),
-    Infix(Infix, Box<Expression>, Box<Expression>),
-    Index(Box<Expression>, Box<Expression>),
+    Literal(Literal, Span),
+    Prefix(Prefix, Box<Expression>, Span),
+    Infix(Infix, Box<Expression>, Box<Expression>, Span),
+    Index(Box<Expression>, Box<Expression>, Span),
+    /// A string with `${}` interpolations, as the sequence of its text fragments
+    /// and embedded expressions.
+    Interpolation(Vec<Expression>, Span),
     If {
         condition: Box<Expression>,
         consequence: BlockOfStatements,
         alternative: Option<BlockOfStatements>,
+        /// Where the `else` keyword is, when there is one.
+        else_span: Option<Span>,
+        span: Span,
     },
 
     Function {
         params: Vec<Identifier>,
         body: BlockOfStatements,
+        span: Span,
     },
     Call {
         func: Box<Expression>,
         args: Option<Vec<Expression>>,
+        span: Span,
     },
 }
 
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Identifier(ident) => ident.span(),
+            Expression::Literal(_, span)
+            | Expression::Prefix(_, _, span)
+            | Expression::Infix(_, _, _, span)
+            | Expression::Index(_, _, span)
+            | Expression::Interpolation(_, span)
+            | Expression::If { span, .. }
+            | Expression::Function { span, .. }
+            | Expression::Call { span, .. } => *span,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum Statement {
     // Blank,
-    Let { name: Identifier, value: Expression },
-    Return { return_value: Expression },
-    Expression { expression: Expression },
+    Let {
+        name: Identifier,
+        value: Expression,
+        /// The `///` comment written above the binding, if any.
+        doc: Option<String>,
+        span: Span,
+    },
+    Return {
+        return_value: Expression,
+        span: Span,
+    },
+    Expression {
+        expression: Expression,
+        span: Span,
+    },
+    While {
+        condition: Expression,
+        body: BlockOfStatements,
+        span: Span,
+    },
+    For {
+        variable: Identifier,
+        iterable: Expression,
+        body: BlockOfStatements,
+        span: Span,
+    },
+    /// `target = value`, or `target += value` and friends when `operator` is set.
+    /// The target is a name or an index expression rooted at a name.
+    Assign {
+        target: Expression,
+        operator: Option<Infix>,
+        value: Expression,
+        span: Span,
+    },
+    Break(Span),
+    Continue(Span),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Let { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::Expression { span, .. }
+            | Statement::While { span, .. }
+            | Statement::For { span, .. }
+            | Statement::Assign { span, .. }
+            | Statement::Break(span)
+            | Statement::Continue(span) => *span,
+        }
+    }
 }
 
 pub type BlockOfStatements = Vec<Statement>;
@@ -1,11 +1,18 @@
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum Precedence {
     Lowest,
+    LogicalOr,   // ||
+    LogicalAnd,  // &&
     Equals,      // ==
     LessGreater, // > or <
+    BitOr,       // |
+    BitXor,      // ^
+    BitAnd,      // &
+    Shift,       // << or >>
     Sum,         // +
     Product,     // *
     Prefix,      // -X or !X
+    Power,       // **
     Call,        // myFunction(x)
     Index,       // array[index]
 }
@@ -0,0 +1,179 @@
+//! Read-only traversal of the tree. A `Visitor` overrides the `visit_*`
+//! methods for the nodes it cares about; the defaults call the matching
+//! `walk_*` function, which visits each child in source order. An override
+//! calls `walk_*` itself to keep descending.
+
+use crate::tkn::Span;
+
+use super::{Expression, Identifier, Literal, Statement};
+
+pub trait Visitor {
+    fn visit_block(&mut self, block: &[Statement]) {
+        walk_block(self, block)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_literal(&mut self, literal: &Literal, _span: Span) {
+        walk_literal(self, literal)
+    }
+
+    /// Called for names being read as well as names being bound: `let` and
+    /// `for` variables and function parameters.
+    fn visit_identifier(&mut self, _ident: &Identifier) {}
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &[Statement]) {
+    for statement in block {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Let { name, value, .. } => {
+            visitor.visit_identifier(name);
+            visitor.visit_expression(value);
+        }
+        Statement::Return { return_value, .. } => visitor.visit_expression(return_value),
+        Statement::Expression { expression, .. } => visitor.visit_expression(expression),
+        Statement::While {
+            condition, body, ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(body);
+        }
+        Statement::For {
+            variable,
+            iterable,
+            body,
+            ..
+        } => {
+            visitor.visit_identifier(variable);
+            visitor.visit_expression(iterable);
+            visitor.visit_block(body);
+        }
+        Statement::Assign { target, value, .. } => {
+            visitor.visit_expression(target);
+            visitor.visit_expression(value);
+        }
+        Statement::Break(_) | Statement::Continue(_) => {}
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Identifier(ident) => visitor.visit_identifier(ident),
+        Expression::Literal(literal, span) => visitor.visit_literal(literal, *span),
+        Expression::Prefix(_, right, _) => visitor.visit_expression(right),
+        Expression::Infix(_, left, right, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Index(left, index, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(index);
+        }
+        Expression::Interpolation(parts, _) => {
+            for part in parts {
+                visitor.visit_expression(part);
+            }
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(consequence);
+            if let Some(alternative) = alternative {
+                visitor.visit_block(alternative);
+            }
+        }
+        Expression::Function { params, body, .. } => {
+            for param in params {
+                visitor.visit_identifier(param);
+            }
+            visitor.visit_block(body);
+        }
+        Expression::Call { func, args, .. } => {
+            visitor.visit_expression(func);
+            for arg in args.iter().flatten() {
+                visitor.visit_expression(arg);
+            }
+        }
+    }
+}
+
+pub fn walk_literal<V: Visitor + ?Sized>(visitor: &mut V, literal: &Literal) {
+    match literal {
+        Literal::Array(items) => {
+            for item in items {
+                visitor.visit_expression(item);
+            }
+        }
+        Literal::Hash(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Literal::Int { .. }
+        | Literal::BigInt { .. }
+        | Literal::Float { .. }
+        | Literal::String { .. }
+        | Literal::Bool(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod visit_test {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    /// Collects every name along with the number of calls it appears in.
+    #[derive(Default)]
+    struct Names {
+        names: Vec<String>,
+        calls: usize,
+    }
+
+    impl Visitor for Names {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Call { .. } = expression {
+                self.calls += 1;
+            }
+            walk_expression(self, expression)
+        }
+
+        fn visit_identifier(&mut self, ident: &Identifier) {
+            self.names.push(ident.literal.clone());
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let input = "let add = func(a, b) { a + b };
+for x in [add(1, 2), {\"k\": y}] { if x { z[0] = \"${w}\" } else { break } }
+while !done { putln(add(v, -u)) }";
+        let program = Parser::new(Lexer::new(input)).parse_program();
+
+        let mut names = Names::default();
+        names.visit_block(&program);
+        assert_eq!(
+            names.names,
+            [
+                "add", "a", "b", "a", "b", "x", "add", "y", "x", "z", "w", "done", "putln", "add",
+                "v", "u"
+            ]
+        );
+        assert_eq!(names.calls, 3);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::tkn::Span;
+
+const RED: &str = "\x1B[1;31m";
+const BLUE: &str = "\x1B[1;34m";
+const BOLD: &str = "\x1B[1m";
+const RESET: &str = "\x1B[0m";
+
+/// Returns true when diagnostics written to the terminal may use ANSI colours.
+pub fn use_colour() -> bool {
+    use std::io::IsTerminal;
+
+    std::env::var_os("NO_COLOR").is_none()
+        && std::io::stdout().is_terminal()
+        && std::io::stderr().is_terminal()
+}
+
+/// Renders `message` together with the line of `source` that `span` points at,
+/// underlining the offending range:
+///
+/// ```text
+/// error: expected `)`, found end of input
+///  --> line 1:15
+///   |
+/// 1 | let y = (1 + 2;
+///   |               ^
+///   = help: ...
+/// ```
+pub fn render(source: &str, span: Span, message: &str, help: Option<&str>, colour: bool) -> String {
+    let paint = |style: &str, text: &str| {
+        if colour {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let start = span.start.min(source.len());
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    let line_no = span.line.to_string();
+    let gutter = " ".repeat(line_no.len());
+    let offset = source[line_start..start].chars().count();
+    let width = source[start..span.end.clamp(start, line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    let mut out = format!("{}: {}\n", paint(RED, "error"), paint(BOLD, message));
+    out += &format!("{}{} {}\n", gutter, paint(BLUE, "-->"), span);
+    out += &format!("{} {}\n", gutter, paint(BLUE, "|"));
+    out += &format!("{} {} {}\n", paint(BLUE, &line_no), paint(BLUE, "|"), line);
+    out += &format!(
+        "{} {} {}{}",
+        gutter,
+        paint(BLUE, "|"),
+        " ".repeat(offset),
+        paint(RED, &"^".repeat(width))
+    );
+    if let Some(help) = help {
+        out += &format!("\n{} {} help: {}", gutter, paint(BLUE, "="), help);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod diagnostic_test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let source = "let x = 5;\nlet y = (1 + 2;";
+        let span = Span::new(25, 26, 2, 15);
+        let rendered = render(source, span, "expected `)`", Some("close it"), false);
+        assert_eq!(
+            rendered,
+            "error: expected `)`
+ --> line 2:15
+  |
+2 | let y = (1 + 2;
+  |               ^
+  = help: close it"
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let source = "(1 + 2";
+        let span = Span::new(6, 6, 1, 7);
+        let rendered = render(source, span, "unclosed", None, false);
+        assert!(
+            rendered.ends_with("1 | (1 + 2\n  |       ^"),
+            "{}",
+            rendered
+        );
+    }
+}
@@ -0,0 +1,843 @@
+//! `--dump-tokens`, `--dump-ast` and `--load-ast`: tokens and syntax trees as
+//! JSON or S-expressions for editor tooling, and programs built by other tools
+//! read back from JSON.
+//!
+//! Every tree node is an object with a `type`, its fields and a `span` holding
+//! `start` and `end` byte offsets and the `line` and `column` where it starts.
+//! A program is an array of statements. When loading, spans may be left out.
+
+mod json;
+mod sexp;
+
+use std::{
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+use crate::{
+    abstract_tree::{
+        visit::{self, Visitor},
+        BlockOfStatements, Expression, Identifier, Infix, Literal, Prefix, Program, SourceWriter,
+        Statement,
+    },
+    diagnostic,
+    evaluator::BigInt,
+    lexer::Lexer,
+    parser::Parser,
+    runner::{EXIT_FAILURE, EXIT_USAGE},
+    tkn::{Span, Token, TokenKind},
+};
+
+pub use json::Json;
+
+const INFIXES: [Infix; 19] = [
+    Infix::Plus,
+    Infix::Minus,
+    Infix::Divide,
+    Infix::Multiply,
+    Infix::Modulo,
+    Infix::Power,
+    Infix::And,
+    Infix::Or,
+    Infix::BitAnd,
+    Infix::BitOr,
+    Infix::BitXor,
+    Infix::ShiftLeft,
+    Infix::ShiftRight,
+    Infix::Equal,
+    Infix::NotEqual,
+    Infix::GreaterThanEqual,
+    Infix::GreaterThan,
+    Infix::LessThanEqual,
+    Infix::LessThan,
+];
+const PREFIXES: [Prefix; 3] = [Prefix::Plus, Prefix::Minus, Prefix::Not];
+
+/// Runs one of the dump options, e.g. `--dump-ast=sexp`, on the file in `args`.
+/// A path of `-` reads standard input.
+pub fn run(option: &str, args: &[String]) -> ExitCode {
+    let (name, format) = match option.split_once('=') {
+        Some((name, format)) => (name, Some(format)),
+        None => (option, None),
+    };
+    let sexp = match (name, format) {
+        ("--dump-tokens" | "--dump-ast", None | Some("json")) => false,
+        ("--dump-tokens" | "--dump-ast", Some("sexp")) => true,
+        ("--load-ast", None) => false,
+        _ => return usage(&format!("unknown option `{}`", option)),
+    };
+    let [path] = args else {
+        return usage(&format!("`{}` needs exactly one file", name));
+    };
+    let text = match read(path) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("racoon: can't read {}: {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let output = match name {
+        "--dump-tokens" => Ok(tokens_to_json(&text)),
+        "--dump-ast" => parse(&text).map(|program| program_to_json(&program)),
+        _ => match json::parse(&text).and_then(|json| program_from_json(&json)) {
+            Ok(program) => {
+                let _ = write!(io::stdout(), "{}", SourceWriter::new().program(&program));
+                return ExitCode::SUCCESS;
+            }
+            Err(error) => Err(error),
+        },
+    };
+    match output {
+        Ok(json) => {
+            let text = if sexp {
+                sexp::to_sexp(&json)
+            } else {
+                json.pretty()
+            };
+            // Dumps are often piped into `head` and the like, which may close
+            // the pipe early.
+            let _ = writeln!(io::stdout(), "{}", text);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
+
+fn usage(message: &str) -> ExitCode {
+    eprintln!("racoon: {}", message);
+    eprintln!("usage: racoon --dump-tokens[=json|sexp] file");
+    eprintln!("       racoon --dump-ast[=json|sexp] file");
+    eprintln!("       racoon --load-ast file.json");
+    ExitCode::from(EXIT_USAGE)
+}
+
+fn read(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+fn parse(source: &str) -> Result<Program, String> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if parser.get_errors().is_empty() {
+        return Ok(program);
+    }
+    let colour = diagnostic::use_colour();
+    let errors = parser
+        .get_errors()
+        .iter()
+        .map(|error| error.render(source, colour))
+        .collect::<Vec<_>>();
+    Err(errors.join("\n\n"))
+}
+
+/// Every token in `source` up to the end of input, which isn't included.
+pub fn tokens_to_json(source: &str) -> Json {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = vec![];
+    loop {
+        let tok = lexer.next_token();
+        if tok.kind == TokenKind::EOF {
+            return Json::Array(tokens);
+        }
+        tokens.push(token_to_json(&tok));
+    }
+}
+
+fn token_to_json(tok: &Token) -> Json {
+    // `TRUE(true)` and `FALSE(false)` are named without their payload.
+    let kind = format!("{:?}", tok.kind);
+    let kind = kind.split('(').next().unwrap_or_default();
+    Json::object([
+        ("kind", string(kind)),
+        ("literal", string(&tok.literal)),
+        ("span", span_to_json(tok.span)),
+    ])
+}
+
+fn span_to_json(span: Span) -> Json {
+    Json::object([
+        ("start", Json::Int(span.start as i64)),
+        ("end", Json::Int(span.end as i64)),
+        ("line", Json::Int(span.line as i64)),
+        ("column", Json::Int(span.column as i64)),
+    ])
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_string())
+}
+
+pub fn program_to_json(program: &[Statement]) -> Json {
+    Json::Array(program.iter().map(statement_to_json).collect())
+}
+
+/// Builds a tree node: its type, its fields and its span.
+fn node<const N: usize>(kind: &str, fields: [(&str, Json); N], span: Span) -> Json {
+    let mut members = vec![(String::from("type"), string(kind))];
+    members.extend(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value)),
+    );
+    members.push((String::from("span"), span_to_json(span)));
+    Json::Object(members)
+}
+
+fn statement_to_json(statement: &Statement) -> Json {
+    match statement {
+        Statement::Let {
+            name,
+            value,
+            doc,
+            span,
+        } => node(
+            "Let",
+            [
+                ("name", identifier_to_json(name)),
+                ("value", expression_to_json(value)),
+                ("doc", doc.as_deref().map_or(Json::Null, string)),
+            ],
+            *span,
+        ),
+        Statement::Return { return_value, span } => node(
+            "Return",
+            [("value", expression_to_json(return_value))],
+            *span,
+        ),
+        Statement::Expression { expression, span } => node(
+            "ExpressionStatement",
+            [("expression", expression_to_json(expression))],
+            *span,
+        ),
+        Statement::While {
+            condition,
+            body,
+            span,
+        } => node(
+            "While",
+            [
+                ("condition", expression_to_json(condition)),
+                ("body", program_to_json(body)),
+            ],
+            *span,
+        ),
+        Statement::For {
+            variable,
+            iterable,
+            body,
+            span,
+        } => node(
+            "For",
+            [
+                ("variable", identifier_to_json(variable)),
+                ("iterable", expression_to_json(iterable)),
+                ("body", program_to_json(body)),
+            ],
+            *span,
+        ),
+        Statement::Assign {
+            target,
+            operator,
+            value,
+            span,
+        } => node(
+            "Assign",
+            [
+                ("target", expression_to_json(target)),
+                (
+                    "operator",
+                    operator
+                        .as_ref()
+                        .map_or(Json::Null, |op| string(&op.to_string())),
+                ),
+                ("value", expression_to_json(value)),
+            ],
+            *span,
+        ),
+        Statement::Break(span) => node("Break", [], *span),
+        Statement::Continue(span) => node("Continue", [], *span),
+    }
+}
+
+fn identifier_to_json(ident: &Identifier) -> Json {
+    node(
+        "Identifier",
+        [("name", string(&ident.literal))],
+        ident.span(),
+    )
+}
+
+fn expressions_to_json(expressions: &[Expression]) -> Json {
+    Json::Array(expressions.iter().map(expression_to_json).collect())
+}
+
+fn expression_to_json(expression: &Expression) -> Json {
+    match expression {
+        Expression::Identifier(ident) => identifier_to_json(ident),
+        Expression::Literal(literal, span) => literal_to_json(literal, *span),
+        Expression::Prefix(prefix, right, span) => node(
+            "Prefix",
+            [
+                ("operator", string(&prefix.to_string())),
+                ("right", expression_to_json(right)),
+            ],
+            *span,
+        ),
+        Expression::Infix(infix, left, right, span) => node(
+            "Infix",
+            [
+                ("operator", string(&infix.to_string())),
+                ("left", expression_to_json(left)),
+                ("right", expression_to_json(right)),
+            ],
+            *span,
+        ),
+        Expression::Index(left, index, span) => node(
+            "Index",
+            [
+                ("left", expression_to_json(left)),
+                ("index", expression_to_json(index)),
+            ],
+            *span,
+        ),
+        Expression::Interpolation(parts, span) => node(
+            "Interpolation",
+            [("parts", expressions_to_json(parts))],
+            *span,
+        ),
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+            else_span,
+            span,
+        } => node(
+            "If",
+            [
+                ("condition", expression_to_json(condition)),
+                ("consequence", program_to_json(consequence)),
+                (
+                    "alternative",
+                    alternative.as_deref().map_or(Json::Null, program_to_json),
+                ),
+                ("elseSpan", else_span.map_or(Json::Null, span_to_json)),
+            ],
+            *span,
+        ),
+        Expression::Function { params, body, span } => node(
+            "Function",
+            [
+                (
+                    "params",
+                    Json::Array(params.iter().map(identifier_to_json).collect()),
+                ),
+                ("body", program_to_json(body)),
+            ],
+            *span,
+        ),
+        Expression::Call { func, args, span } => node(
+            "Call",
+            [
+                ("function", expression_to_json(func)),
+                (
+                    "arguments",
+                    expressions_to_json(args.as_deref().unwrap_or_default()),
+                ),
+            ],
+            *span,
+        ),
+    }
+}
+
+fn literal_to_json(literal: &Literal, span: Span) -> Json {
+    match literal {
+        Literal::Int { token, value } => node(
+            "Int",
+            [
+                ("value", Json::Int(*value)),
+                ("literal", string(&token.literal)),
+            ],
+            span,
+        ),
+        // Big integers are written as strings of decimal digits, which any JSON reader
+        // can hold without losing precision.
+        Literal::BigInt { token, value } => node(
+            "Int",
+            [
+                ("value", string(&value.to_string())),
+                ("literal", string(&token.literal)),
+            ],
+            span,
+        ),
+        Literal::Float { token, value } => node(
+            "Float",
+            [
+                ("value", Json::Float(*value)),
+                ("literal", string(&token.literal)),
+            ],
+            span,
+        ),
+        Literal::String { token, value } => node(
+            "String",
+            [
+                ("value", string(value)),
+                ("literal", string(&token.literal)),
+            ],
+            span,
+        ),
+        Literal::Bool(b) => node("Bool", [("value", Json::Bool(*b))], span),
+        Literal::Array(items) => node("Array", [("elements", expressions_to_json(items))], span),
+        Literal::Hash(pairs) => {
+            let pairs = pairs
+                .iter()
+                .map(|(key, value)| {
+                    Json::Array(vec![expression_to_json(key), expression_to_json(value)])
+                })
+                .collect();
+            node("Hash", [("pairs", Json::Array(pairs))], span)
+        }
+    }
+}
+
+/// The S-expression form of `program`, as `--dump-ast=sexp` writes it.
+pub fn program_to_sexp(program: &[Statement]) -> String {
+    sexp::to_sexp(&program_to_json(program))
+}
+
+/// Reads a program written by `program_to_json`, or built the same way.
+pub fn program_from_json(json: &Json) -> Result<Program, String> {
+    let program = block(json)?;
+    let mut check = Check::default();
+    check.visit_block(&program);
+    match check.error {
+        Some(error) => Err(error),
+        None => Ok(program),
+    }
+}
+
+/// Finds what the parser would have rejected in a tree that didn't come from
+/// source: `break` or `continue` outside a loop and assignments to anything but
+/// a name or an index into one.
+#[derive(Default)]
+struct Check {
+    /// Number of loops around the current statement within the current function.
+    loop_depth: usize,
+    error: Option<String>,
+}
+
+impl Visitor for Check {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Break(_) | Statement::Continue(_) if self.loop_depth == 0 => {
+                let keyword = match statement {
+                    Statement::Break(_) => "break",
+                    _ => "continue",
+                };
+                self.error
+                    .get_or_insert(format!("`{}` outside of a loop", keyword));
+            }
+            Statement::Assign { target, .. } if !Parser::is_assignable(target) => {
+                self.error
+                    .get_or_insert(format!("cannot assign to `{}`", target));
+            }
+            Statement::While { .. } | Statement::For { .. } => {
+                self.loop_depth += 1;
+                visit::walk_statement(self, statement);
+                self.loop_depth -= 1;
+            }
+            _ => visit::walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        // `break` and `continue` never reach a loop outside of the function.
+        let loop_depth = match expression {
+            Expression::Function { .. } => std::mem::take(&mut self.loop_depth),
+            _ => self.loop_depth,
+        };
+        visit::walk_expression(self, expression);
+        self.loop_depth = loop_depth;
+    }
+}
+
+fn block(json: &Json) -> Result<BlockOfStatements, String> {
+    match json {
+        Json::Array(items) => items.iter().map(statement_from_json).collect(),
+        _ => Err(format!("expected an array of statements, found {}", json)),
+    }
+}
+
+/// The `type` of a node along with a way to look up its fields.
+struct Node<'a> {
+    json: &'a Json,
+    kind: &'a str,
+}
+
+impl<'a> Node<'a> {
+    fn new(json: &'a Json) -> Result<Self, String> {
+        match json.get("type") {
+            Some(Json::String(kind)) => Ok(Node { json, kind }),
+            _ => Err(format!("expected a node with a `type`, found {}", json)),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<&'a Json, String> {
+        self.json
+            .get(name)
+            .ok_or_else(|| format!("{} is missing the field `{}`", self.kind, name))
+    }
+
+    fn string(&self, name: &str) -> Result<&'a str, String> {
+        match self.field(name)? {
+            Json::String(s) => Ok(s),
+            other => Err(self.mismatch(name, "a string", other)),
+        }
+    }
+
+    fn expression(&self, name: &str) -> Result<Expression, String> {
+        expression_from_json(self.field(name)?)
+    }
+
+    fn expressions(&self, name: &str) -> Result<Vec<Expression>, String> {
+        match self.field(name)? {
+            Json::Array(items) => items.iter().map(expression_from_json).collect(),
+            other => Err(self.mismatch(name, "an array", other)),
+        }
+    }
+
+    fn identifier(&self, name: &str) -> Result<Identifier, String> {
+        identifier_from_json(self.field(name)?)
+    }
+
+    fn block(&self, name: &str) -> Result<BlockOfStatements, String> {
+        block(self.field(name)?)
+    }
+
+    /// Fields that may be `null` or left out.
+    fn optional(&self, name: &str) -> Option<&'a Json> {
+        self.json.get(name).filter(|value| **value != Json::Null)
+    }
+
+    /// The node's span, or an empty one if it has none.
+    fn span(&self) -> Result<Span, String> {
+        Ok(self.optional_span("span")?.unwrap_or_default())
+    }
+
+    fn optional_span(&self, field: &str) -> Result<Option<Span>, String> {
+        let Some(span) = self.optional(field) else {
+            return Ok(None);
+        };
+        let number = |name: &str| match span.get(name) {
+            Some(Json::Int(n)) if *n >= 0 => Ok(*n as usize),
+            _ => Err(format!(
+                "the `{}` of {} needs a number `{}`",
+                field, self.kind, name
+            )),
+        };
+        Ok(Some(Span::new(
+            number("start")?,
+            number("end")?,
+            number("line")?,
+            number("column")?,
+        )))
+    }
+
+    fn mismatch(&self, name: &str, expected: &str, found: &Json) -> String {
+        format!(
+            "the field `{}` of {} should be {}, found {}",
+            name, self.kind, expected, found
+        )
+    }
+}
+
+fn statement_from_json(json: &Json) -> Result<Statement, String> {
+    let node = Node::new(json)?;
+    let span = node.span()?;
+    Ok(match node.kind {
+        "Let" => Statement::Let {
+            name: node.identifier("name")?,
+            value: node.expression("value")?,
+            doc: match node.optional("doc") {
+                Some(Json::String(doc)) => Some(doc.clone()),
+                Some(other) => return Err(node.mismatch("doc", "a string", other)),
+                None => None,
+            },
+            span,
+        },
+        "Return" => Statement::Return {
+            return_value: node.expression("value")?,
+            span,
+        },
+        "ExpressionStatement" => Statement::Expression {
+            expression: node.expression("expression")?,
+            span,
+        },
+        "While" => Statement::While {
+            condition: node.expression("condition")?,
+            body: node.block("body")?,
+            span,
+        },
+        "For" => Statement::For {
+            variable: node.identifier("variable")?,
+            iterable: node.expression("iterable")?,
+            body: node.block("body")?,
+            span,
+        },
+        "Assign" => Statement::Assign {
+            target: node.expression("target")?,
+            operator: match node.optional("operator") {
+                Some(Json::String(op)) => Some(operator(&INFIXES, op, "an assignment")?),
+                Some(other) => return Err(node.mismatch("operator", "a string", other)),
+                None => None,
+            },
+            value: node.expression("value")?,
+            span,
+        },
+        "Break" => Statement::Break(span),
+        "Continue" => Statement::Continue(span),
+        // Anything else is an expression used as a statement.
+        _ => Statement::Expression {
+            expression: expression_from_json(json)?,
+            span,
+        },
+    })
+}
+
+fn identifier_from_json(json: &Json) -> Result<Identifier, String> {
+    let node = Node::new(json)?;
+    if node.kind != "Identifier" {
+        return Err(format!("expected an Identifier, found {}", node.kind));
+    }
+    let name = node.string("name")?;
+    Ok(Identifier {
+        literal: name.to_string(),
+        token: token(TokenKind::IDENT, name, node.span()?),
+    })
+}
+
+fn expression_from_json(json: &Json) -> Result<Expression, String> {
+    let node = Node::new(json)?;
+    let span = node.span()?;
+    let literal = |literal| Ok(Expression::Literal(literal, span));
+    match node.kind {
+        "Identifier" => Ok(Expression::Identifier(identifier_from_json(json)?)),
+        "Int" => match node.field("value")? {
+            Json::Int(value) => literal(Literal::Int {
+                token: token(TokenKind::INT, literal_text(&node)?, span),
+                value: *value,
+            }),
+            json @ Json::String(digits) => match BigInt::parse(digits) {
+                Some(value) => literal(Literal::BigInt {
+                    token: token(TokenKind::INT, literal_text(&node)?, span),
+                    value,
+                }),
+                None => Err(node.mismatch("value", "an integer", json)),
+            },
+            other => Err(node.mismatch("value", "an integer", other)),
+        },
+        "Float" => match node.field("value")? {
+            Json::Float(value) => literal(Literal::Float {
+                token: token(TokenKind::FLOAT, literal_text(&node)?, span),
+                value: *value,
+            }),
+            Json::Int(value) => literal(Literal::Float {
+                token: token(TokenKind::FLOAT, literal_text(&node)?, span),
+                value: *value as f64,
+            }),
+            other => Err(node.mismatch("value", "a number", other)),
+        },
+        "String" => literal(Literal::String {
+            token: token(TokenKind::STRING, literal_text(&node)?, span),
+            value: node.string("value")?.to_string(),
+        }),
+        "Bool" => match node.field("value")? {
+            Json::Bool(b) => literal(Literal::Bool(*b)),
+            other => Err(node.mismatch("value", "a boolean", other)),
+        },
+        "Array" => literal(Literal::Array(node.expressions("elements")?)),
+        "Hash" => {
+            let pairs = match node.field("pairs")? {
+                Json::Array(pairs) => pairs
+                    .iter()
+                    .map(|pair| match pair {
+                        Json::Array(pair) if pair.len() == 2 => Ok((
+                            expression_from_json(&pair[0])?,
+                            expression_from_json(&pair[1])?,
+                        )),
+                        other => Err(node.mismatch("pairs", "[key, value] arrays", other)),
+                    })
+                    .collect::<Result<_, _>>()?,
+                other => return Err(node.mismatch("pairs", "an array", other)),
+            };
+            literal(Literal::Hash(pairs))
+        }
+        "Prefix" => Ok(Expression::Prefix(
+            operator(&PREFIXES, node.string("operator")?, "a prefix")?,
+            Box::new(node.expression("right")?),
+            span,
+        )),
+        "Infix" => Ok(Expression::Infix(
+            operator(&INFIXES, node.string("operator")?, "an infix")?,
+            Box::new(node.expression("left")?),
+            Box::new(node.expression("right")?),
+            span,
+        )),
+        "Index" => Ok(Expression::Index(
+            Box::new(node.expression("left")?),
+            Box::new(node.expression("index")?),
+            span,
+        )),
+        "Interpolation" => Ok(Expression::Interpolation(node.expressions("parts")?, span)),
+        "If" => Ok(Expression::If {
+            condition: Box::new(node.expression("condition")?),
+            consequence: node.block("consequence")?,
+            alternative: node.optional("alternative").map(block).transpose()?,
+            else_span: node.optional_span("elseSpan")?,
+            span,
+        }),
+        "Function" => Ok(Expression::Function {
+            params: match node.field("params")? {
+                Json::Array(params) => params
+                    .iter()
+                    .map(identifier_from_json)
+                    .collect::<Result<_, _>>()?,
+                other => return Err(node.mismatch("params", "an array", other)),
+            },
+            body: node.block("body")?,
+            span,
+        }),
+        "Call" => Ok(Expression::Call {
+            func: Box::new(node.expression("function")?),
+            args: Some(node.expressions("arguments")?),
+            span,
+        }),
+        kind => Err(format!("unknown node type `{}`", kind)),
+    }
+}
+
+/// The source text of a number, which may be left out to have it written
+/// from its value.
+fn literal_text<'a>(node: &Node<'a>) -> Result<&'a str, String> {
+    match node.optional("literal") {
+        Some(Json::String(literal)) => Ok(literal),
+        Some(other) => Err(node.mismatch("literal", "a string", other)),
+        None => Ok(""),
+    }
+}
+
+fn operator<T: ToString + Clone>(operators: &[T], name: &str, what: &str) -> Result<T, String> {
+    operators
+        .iter()
+        .find(|op| op.to_string() == name)
+        .cloned()
+        .ok_or_else(|| format!("`{}` is not {} operator", name, what))
+}
+
+fn token(kind: TokenKind, literal: &str, span: Span) -> Token {
+    Token {
+        kind,
+        literal: literal.to_string(),
+        span,
+    }
+}
+
+#[cfg(test)]
+mod dump_test {
+    use super::*;
+
+    #[test]
+    fn test_tokens_to_json() {
+        let tokens = tokens_to_json("let x = true;");
+        assert_eq!(
+            tokens.to_string(),
+            concat!(
+                r#"[{"kind": "LET", "literal": "let", "span": {"start": 0, "end": 3, "line": 1, "column": 1}}, "#,
+                r#"{"kind": "IDENT", "literal": "x", "span": {"start": 4, "end": 5, "line": 1, "column": 5}}, "#,
+                r#"{"kind": "ASSIGN", "literal": "=", "span": {"start": 6, "end": 7, "line": 1, "column": 7}}, "#,
+                r#"{"kind": "TRUE", "literal": "true", "span": {"start": 8, "end": 12, "line": 1, "column": 9}}, "#,
+                r#"{"kind": "SEMICOLON", "literal": ";", "span": {"start": 12, "end": 13, "line": 1, "column": 13}}]"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_round_trip() {
+        let source = "/// Doubles.
+let double = func(x) { return x * 2; };
+let items = [1, 2.50, 0x1F, 0x1_0000_0000_0000_0000, r\"raw\", \"a${double(2)}\", {true: -x}];
+for item in items { if !item { continue } else { break } }
+while false { items[0] += 1 }
+";
+        let program = parse(source).unwrap();
+        let json = program_to_json(&program);
+        assert_eq!(program_from_json(&json).unwrap(), program);
+        assert_eq!(
+            program_from_json(&json::parse(&json.pretty()).unwrap()).unwrap(),
+            program
+        );
+
+        let sexp = program_to_sexp(&parse("a + 1").unwrap());
+        assert!(
+            sexp.starts_with("[(ExpressionStatement\n   :expression (Infix"),
+            "{}",
+            sexp
+        );
+    }
+
+    #[test]
+    fn test_program_from_json() {
+        // Spans and the source text of numbers can be left out.
+        let json = json::parse(
+            r#"[{"type": "Let", "name": {"type": "Identifier", "name": "x"},
+                 "value": {"type": "Infix", "operator": "+",
+                           "left": {"type": "Int", "value": 1},
+                           "right": {"type": "Float", "value": 2}}},
+                {"type": "Call", "function": {"type": "Identifier", "name": "putln"},
+                 "arguments": [{"type": "Identifier", "name": "x"}]}]"#,
+        )
+        .unwrap();
+        let program = program_from_json(&json).unwrap();
+        assert_eq!(
+            SourceWriter::new().program(&program),
+            "let x = 1 + 2.0;\nputln(x);\n"
+        );
+
+        for (input, error) in [
+            ("{}", "expected an array of statements, found {}"),
+            (
+                "[{\"type\": \"Return\"}]",
+                "Return is missing the field `value`",
+            ),
+            (
+                "[{\"type\": \"Prefix\", \"operator\": \"~\", \"right\": null}]",
+                "`~` is not a prefix operator",
+            ),
+            ("[{\"type\": \"Loop\"}]", "unknown node type `Loop`"),
+            ("[{\"type\": \"Break\"}]", "`break` outside of a loop"),
+            (
+                r#"[{"type": "While", "condition": {"type": "Bool", "value": true}, "body": [
+                    {"type": "Let", "name": {"type": "Identifier", "name": "f"},
+                     "value": {"type": "Function", "params": [], "body": [{"type": "Continue"}]}}]}]"#,
+                "`continue` outside of a loop",
+            ),
+            (
+                r#"[{"type": "Assign", "target": {"type": "Int", "value": 1},
+                     "value": {"type": "Int", "value": 2}}]"#,
+                "cannot assign to `1`",
+            ),
+        ] {
+            let json = json::parse(input).unwrap();
+            assert_eq!(program_from_json(&json).unwrap_err(), error, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Writes the JSON form of tokens and syntax trees as S-expressions, which are
+//! easier to read when debugging the parser. A tree node, an object with a
+//! `type`, becomes `(Type :field value ...)`, any other object becomes a list
+//! of `:field value` pairs and an array becomes `[...]`.
+
+use super::json::Json;
+
+/// Longest list written on a single line.
+const WIDTH: usize = 80;
+
+pub fn to_sexp(value: &Json) -> String {
+    let mut out = String::new();
+    write(value, &mut out);
+    out
+}
+
+fn write(value: &Json, out: &mut String) {
+    let line = flat(value);
+    let column = out.len() - out.rfind('\n').map_or(0, |newline| newline + 1);
+    let (open, close, head, items) = match value {
+        Json::Array(items) if !items.is_empty() => (
+            '[',
+            ']',
+            None,
+            items.iter().map(|item| (None, item)).collect(),
+        ),
+        Json::Object(members) if !members.is_empty() => {
+            let (head, members) = split_type(members);
+            let items = members
+                .iter()
+                .map(|(key, value)| (Some(key.as_str()), value))
+                .collect::<Vec<_>>();
+            ('(', ')', head, items)
+        }
+        _ => return out.push_str(&line),
+    };
+    if column + line.len() <= WIDTH {
+        return out.push_str(&line);
+    }
+
+    // A node's fields go on lines of their own, indented past its type. In
+    // other lists the first item stays on the opening line and the rest line
+    // up with it.
+    let indent = " ".repeat(column + if head.is_some() { 2 } else { 1 });
+    out.push(open);
+    let mut first = true;
+    if let Some(head) = head {
+        out.push_str(head);
+        first = false;
+    }
+    for (key, item) in items {
+        if first {
+            first = false;
+        } else {
+            out.push('\n');
+            out.push_str(&indent);
+        }
+        if let Some(key) = key {
+            out.push_str(&format!(":{} ", key));
+        }
+        write(item, out);
+    }
+    out.push(close);
+}
+
+fn flat(value: &Json) -> String {
+    match value {
+        Json::Null => String::from("nil"),
+        Json::Array(items) => {
+            let items = items.iter().map(flat).collect::<Vec<_>>();
+            format!("[{}]", items.join(" "))
+        }
+        Json::Object(members) => {
+            let (head, members) = split_type(members);
+            let mut parts = head.map(str::to_string).into_iter().collect::<Vec<_>>();
+            for (key, value) in members {
+                parts.push(format!(":{} {}", key, flat(value)));
+            }
+            format!("({})", parts.join(" "))
+        }
+        // Strings, numbers and booleans are written as in JSON.
+        value => value.to_string(),
+    }
+}
+
+/// Separates the `type` of a tree node from the rest of its fields.
+fn split_type(members: &[(String, Json)]) -> (Option<&str>, Vec<&(String, Json)>) {
+    let head = members
+        .iter()
+        .find_map(|(key, value)| match (key.as_str(), value) {
+            ("type", Json::String(name)) => Some(name.as_str()),
+            _ => None,
+        });
+    let rest = members
+        .iter()
+        .filter(|(key, _)| head.is_none() || key != "type")
+        .collect();
+    (head, rest)
+}
+
+#[cfg(test)]
+mod sexp_test {
+    use super::*;
+
+    #[test]
+    fn test_to_sexp() {
+        let node = Json::object([
+            ("type", Json::String("Call".to_string())),
+            ("name", Json::String("f".to_string())),
+            ("args", Json::Array(vec![Json::Int(1), Json::Null])),
+            (
+                "span",
+                Json::object([("start", Json::Int(0)), ("end", Json::Int(4))]),
+            ),
+        ]);
+        assert_eq!(
+            to_sexp(&node),
+            "(Call :name \"f\" :args [1 nil] :span (:start 0 :end 4))"
+        );
+
+        let long = Json::object([
+            ("type", Json::String("Array".to_string())),
+            (
+                "elements",
+                Json::Array(vec![Json::String("x".repeat(40)); 2]),
+            ),
+            ("span", Json::Null),
+        ]);
+        let x = "x".repeat(40);
+        assert_eq!(
+            to_sexp(&long),
+            format!(
+                "(Array\n  :elements [\"{}\"\n             \"{}\"]\n  :span nil)",
+                x, x
+            )
+        );
+    }
+}
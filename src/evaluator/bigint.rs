@@ -0,0 +1,454 @@
+use std::{cmp::Ordering, fmt};
+
+/// An arbitrary-precision integer, used once a value no longer fits in an `i64`.
+///
+/// The magnitude is stored as base 2^32 limbs, least significant first, and is
+/// always normalised: there are no trailing zero limbs and zero is never negative.
+#[derive(Eq, Hash, PartialEq, Clone, Debug, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn from_i64(value: i64) -> Self {
+        Self::from_parts(value < 0, Self::limbs(value.unsigned_abs()))
+    }
+
+    /// Converts an integral float, returning `None` for NaN and infinities.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let value = value.trunc();
+        if value.abs() < 9.2e18 {
+            return Some(Self::from_i64(value as i64));
+        }
+
+        let bits = value.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as usize - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let magnitude = Self::from_parts(false, Self::limbs(mantissa)).shl(exponent);
+        Some(Self::from_parts(value < 0.0, magnitude.magnitude))
+    }
+
+    /// Parses an optionally signed decimal string.
+    pub fn parse(input: &str) -> Option<Self> {
+        let (negative, digits) = match input.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut magnitude = vec![];
+        for chunk in digits.as_bytes().chunks(9) {
+            let value = chunk
+                .iter()
+                .fold(0u32, |acc, digit| acc * 10 + (digit - b'0') as u32);
+            Self::mul_small_add(&mut magnitude, 10u32.pow(chunk.len() as u32), value);
+        }
+        Some(Self::from_parts(negative, magnitude))
+    }
+
+    /// Parses unsigned digits in the given base, as written after a `0x`, `0o` or
+    /// `0b` prefix.
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude = vec![];
+        for ch in digits.chars() {
+            Self::mul_small_add(&mut magnitude, radix, ch.to_digit(radix)?);
+        }
+        Some(Self::from_parts(false, magnitude))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &limb| (acc << 32) | limb as u64);
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0f64, |acc, &limb| acc * 4294967296.0 + limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Number of significant bits in the magnitude.
+    pub fn bits(&self) -> usize {
+        match self.magnitude.last() {
+            Some(last) => self.magnitude.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::from_parts(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self::from_parts(
+                self.negative,
+                Self::add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+        match Self::cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => Self::from_parts(
+                other.negative,
+                Self::sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => Self::from_parts(
+                self.negative,
+                Self::sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut product = vec![0u32; self.magnitude.len() + other.magnitude.len()];
+        for (i, &a) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.magnitude.iter().enumerate() {
+                let current = product[i + j] as u64 + a as u64 * b as u64 + carry;
+                product[i + j] = current as u32;
+                carry = current >> 32;
+            }
+            product[i + other.magnitude.len()] = carry as u32;
+        }
+        Self::from_parts(self.negative != other.negative, product)
+    }
+
+    /// Truncating division returning the quotient and the remainder, whose sign
+    /// follows the dividend (like `i64`'s `/` and `%`). Returns `None` when
+    /// dividing by zero.
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = if other.magnitude.len() == 1 {
+            let mut quotient = self.magnitude.clone();
+            let remainder = Self::div_rem_small(&mut quotient, other.magnitude[0]);
+            (quotient, Self::limbs(remainder as u64))
+        } else {
+            Self::div_rem_magnitude(&self.magnitude, &other.magnitude)
+        };
+        Some((
+            Self::from_parts(self.negative != other.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u64) -> Self {
+        let mut result = Self::from_i64(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    pub fn shl(&self, bits: usize) -> Self {
+        let mut magnitude = vec![0u32; bits / 32];
+        let shift = bits % 32;
+        let mut carry = 0u32;
+        for &limb in self.magnitude.iter() {
+            if shift == 0 {
+                magnitude.push(limb);
+            } else {
+                magnitude.push((limb << shift) | carry);
+                carry = limb >> (32 - shift);
+            }
+        }
+        magnitude.push(carry);
+        Self::from_parts(self.negative, magnitude)
+    }
+
+    /// Arithmetic shift right, rounding towards negative infinity like `i64`'s `>>`.
+    pub fn shr(&self, bits: usize) -> Self {
+        if self.negative {
+            let one = Self::from_i64(1);
+            return self.add(&one).shr_magnitude(bits).sub(&one);
+        }
+        self.shr_magnitude(bits)
+    }
+
+    fn shr_magnitude(&self, bits: usize) -> Self {
+        let skip = bits / 32;
+        if skip >= self.magnitude.len() {
+            return Self::default();
+        }
+        let shift = bits % 32;
+        let limbs = &self.magnitude[skip..];
+        let magnitude = (0..limbs.len())
+            .map(|i| {
+                let high = limbs.get(i + 1).copied().unwrap_or(0) as u64;
+                ((((high << 32) | limbs[i] as u64) >> shift) & 0xffff_ffff) as u32
+            })
+            .collect();
+        Self::from_parts(self.negative, magnitude)
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    fn limbs(value: u64) -> Vec<u32> {
+        vec![value as u32, (value >> 32) as u32]
+    }
+
+    fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in magnitude.iter_mut() {
+            let current = *limb as u64 * factor as u64 + carry;
+            *limb = current as u32;
+            carry = current >> 32;
+        }
+        if carry > 0 {
+            magnitude.push(carry as u32);
+        }
+    }
+
+    fn div_rem_small(magnitude: &mut [u32], divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in magnitude.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        remainder as u32
+    }
+
+    /// Binary long division of two magnitudes.
+    fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        let mut quotient = vec![0u32; dividend.len()];
+        let mut remainder = BigInt::default();
+        let divisor = Self::from_parts(false, divisor.to_vec());
+        for i in (0..dividend.len() * 32).rev() {
+            remainder = remainder.shl(1);
+            if (dividend[i / 32] >> (i % 32)) & 1 == 1 {
+                remainder = remainder.add(&Self::from_i64(1));
+            }
+            if Self::cmp_magnitude(&remainder.magnitude, &divisor.magnitude) != Ordering::Less {
+                remainder = remainder.sub(&divisor);
+                quotient[i / 32] |= 1 << (i % 32);
+            }
+        }
+        (quotient, remainder.magnitude)
+    }
+
+    fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+        a.len()
+            .cmp(&b.len())
+            .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        let mut sum = Vec::with_capacity(long.len() + 1);
+        let mut carry = 0u64;
+        for (i, &limb) in long.iter().enumerate() {
+            let current = limb as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+            sum.push(current as u32);
+            carry = current >> 32;
+        }
+        sum.push(carry as u32);
+        sum
+    }
+
+    /// Subtracts `b` from `a`, which must not be smaller than `b`.
+    fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut difference = Vec::with_capacity(a.len());
+        let mut borrow = 0i64;
+        for (i, &limb) in a.iter().enumerate() {
+            let mut current = limb as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+            borrow = 0;
+            if current < 0 {
+                current += 1 << 32;
+                borrow = 1;
+            }
+            difference.push(current as u32);
+        }
+        difference
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => Self::cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => Self::cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = vec![];
+        while !magnitude.is_empty() {
+            chunks.push(Self::div_rem_small(&mut magnitude, 1_000_000_000));
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod bigint_test {
+    use super::*;
+
+    fn big(input: &str) -> BigInt {
+        BigInt::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let tests = vec![
+            "0",
+            "1",
+            "-1",
+            "4294967296",
+            "9223372036854775808",
+            "-9223372036854775809",
+            "123456789012345678901234567890123456789",
+        ];
+        for input in tests {
+            assert_eq!(big(input).to_string(), input);
+        }
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("+007").to_string(), "7");
+        assert!(BigInt::parse("12a").is_none());
+        assert!(BigInt::parse("-").is_none());
+
+        let parse = |digits, radix| BigInt::parse_radix(digits, radix).map(|n| n.to_string());
+        assert_eq!(
+            parse("10000000000000000", 16).as_deref(),
+            Some("18446744073709551616")
+        );
+        assert_eq!(parse("777", 8).as_deref(), Some("511"));
+        assert_eq!(parse("0", 2).as_deref(), Some("0"));
+        assert!(parse("12", 2).is_none());
+        assert!(parse("", 16).is_none());
+    }
+
+    #[test]
+    fn test_i64_round_trip() {
+        for value in [0, 1, -1, i64::MAX, i64::MIN, 1 << 40, -(1 << 40)] {
+            assert_eq!(BigInt::from_i64(value).to_i64(), Some(value));
+            assert_eq!(BigInt::from_i64(value).to_string(), value.to_string());
+        }
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!(a.add(&b).to_string(), "-864197532086419753208641975320");
+        assert_eq!(a.sub(&b).to_string(), "1111111110111111111011111111100");
+        assert_eq!(
+            a.mul(&b).to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+        let (q, r) = b.div_rem(&a).unwrap();
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("-8".to_string(), "-9000000000900000000090".to_string())
+        );
+        let (q, r) = a.mul(&a).div_rem(&a).unwrap();
+        assert_eq!((q, r.to_string()), (a.clone(), "0".to_string()));
+        assert!(a.div_rem(&BigInt::default()).is_none());
+        assert_eq!(
+            big("2").pow(100).to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(big("-3").pow(3).to_string(), "-27");
+    }
+
+    #[test]
+    fn test_shifts_and_order() {
+        assert_eq!(big("1").shl(100), big("2").pow(100));
+        assert_eq!(big("2").pow(100).shr(98).to_string(), "4");
+        assert_eq!(big("-5").shr(1).to_string(), "-3");
+        assert_eq!(big("-1").shr(100).to_string(), "-1");
+        assert!(big("-10") < big("-9"));
+        assert!(big("9223372036854775808") > BigInt::from_i64(i64::MAX));
+        assert_eq!(
+            BigInt::from_f64(1e20).unwrap().to_string(),
+            "100000000000000000000"
+        );
+        assert_eq!(BigInt::from_f64(-2.5).unwrap().to_string(), "-2");
+        assert_eq!(big("100000000000000000000").to_f64(), 1e20);
+    }
+}
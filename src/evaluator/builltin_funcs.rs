@@ -1,125 +1,390 @@
 use std::collections::HashMap;
 
-use super::Object;
+use super::{types::BuiltInFunc, BigInt, HashKey, Object, RuntimeError, RuntimeErrorKind};
+
+/// Every builtin as its name, its arity (-1 for any number of arguments) and
+/// its implementation.
+const BUILTINS: [(&str, i16, BuiltInFunc); 19] = [
+    ("len", 1, len),
+    ("head", 1, head),
+    ("last", 1, last),
+    ("tail", 1, tail),
+    ("push", 2, push),
+    ("int", 1, int),
+    ("float", 1, float),
+    ("round", -1, round),
+    ("floor", 1, floor),
+    ("ceil", 1, ceil),
+    ("keys", 1, keys),
+    ("values", 1, values),
+    ("has", 2, has),
+    ("delete", 2, delete),
+    ("merge", 2, merge),
+    ("putln", -1, putln),
+    ("doc", 1, doc),
+    ("repr", 1, repr),
+    ("str", 1, str),
+];
 
 pub fn new_builtins() -> HashMap<String, Object> {
-    let mut builtins = HashMap::new();
-    builtins.insert(
-        String::from("len"),
-        Object::BUILTIN {
-            arity: 1,
-            builtInFunc: len,
-        },
-    );
-    builtins.insert(
-        String::from("head"),
-        Object::BUILTIN {
-            arity: 1,
-            builtInFunc: head,
-        },
-    );
-    builtins.insert(
-        String::from("last"),
-        Object::BUILTIN {
-            arity: 1,
-            builtInFunc: last,
-        },
-    );
-    builtins.insert(
-        String::from("tail"),
-        Object::BUILTIN {
-            arity: 1,
-            builtInFunc: tail,
-        },
-    );
-    builtins.insert(
-        String::from("push"),
-        Object::BUILTIN {
-            arity: 2,
-            builtInFunc: push,
-        },
-    );
-    builtins
+    BUILTINS
+        .iter()
+        .map(|&(name, arity, builtin_func)| {
+            let builtin = Object::BUILTIN {
+                name,
+                arity,
+                builtInFunc: builtin_func,
+            };
+            (name.to_string(), builtin)
+        })
+        .collect()
 }
 
-pub fn len(args: Vec<Object>) -> Object {
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.iter().any(|&(builtin, _, _)| builtin == name)
+}
+
+/// Builds the `TypeError` raised when a builtin receives an argument of the wrong type.
+fn type_error(message: String) -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::TypeMismatch, message)
+}
+
+pub fn len(args: Vec<Object>) -> Result<Object, RuntimeError> {
     if args.len() != 1 {
-        return Object::ERROR(format!("Expected 1 argument but received {}", args.len()));
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArityMismatch,
+            format!("Expected 1 argument but received {}", args.len()),
+        ));
     }
     match &args[0] {
-        Object::STRING(str_val) => Object::INTEGER(str_val.len() as i64),
-        Object::ARRAY(arr) => Object::INTEGER(arr.len() as i64),
-        _ => Object::ERROR(format!(
-            "SyntaxError: len() function cannot be used for {}",
+        Object::STRING(str_val) => Ok(Object::INTEGER(str_val.chars().count() as i64)),
+        Object::ARRAY(arr) => Ok(Object::INTEGER(arr.len() as i64)),
+        Object::HASH(table) => Ok(Object::INTEGER(table.len() as i64)),
+        _ => Err(type_error(format!(
+            "len() function cannot be used for {}",
             Object::type_of(args[0].clone())
-        )),
+        ))),
     }
 }
 
-pub fn head(args: Vec<Object>) -> Object {
+pub fn head(args: Vec<Object>) -> Result<Object, RuntimeError> {
     if args.len() != 1 {
-        return Object::ERROR(format!("Expected 1 argument but received {}", args.len()));
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArityMismatch,
+            format!("Expected 1 argument but received {}", args.len()),
+        ));
     }
     match &args[0] {
-        Object::ARRAY(arr) => {
-            if arr.len()>0{
-                return arr.get(0).unwrap().clone();
-            }
-            Object::ERROR(format!("LogicalError: The array has no elements or hasn't been initialized yet."))
+        Object::ARRAY(arr) => match arr.first() {
+            Some(first) => Ok(first.clone()),
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::IndexOutOfBounds,
+                "The array has no elements or hasn't been initialized yet.",
+            )),
         },
-        _ => Object::ERROR(format!(
-            "SyntaxError: first() function cannot be used for {}. It can only be used for Array type.",
+        _ => Err(type_error(format!(
+            "head() function cannot be used for {}. It can only be used for Array type.",
             Object::type_of(args[0].clone())
-        )),
+        ))),
     }
 }
 
-pub fn last(args: Vec<Object>) -> Object {
+pub fn last(args: Vec<Object>) -> Result<Object, RuntimeError> {
     if args.len() != 1 {
-        return Object::ERROR(format!("Expected 1 argument but received {}", args.len()));
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArityMismatch,
+            format!("Expected 1 argument but received {}", args.len()),
+        ));
     }
     match &args[0] {
-        Object::ARRAY(arr) => {
-            if arr.len()>0{
-                return arr.get(arr.len()-1).unwrap().clone();
-            }
-            Object::ERROR(format!("LogicalError: The array has no elements or hasn't been initialized yet."))
+        Object::ARRAY(arr) => match arr.last() {
+            Some(last) => Ok(last.clone()),
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::IndexOutOfBounds,
+                "The array has no elements or hasn't been initialized yet.",
+            )),
         },
-        _ => Object::ERROR(format!(
-            "SyntaxError: last() function cannot be used for {}. It can only be used for Array type.",
+        _ => Err(type_error(format!(
+            "last() function cannot be used for {}. It can only be used for Array type.",
             Object::type_of(args[0].clone())
-        )),
+        ))),
     }
 }
 
-pub fn tail(args: Vec<Object>) -> Object {
+pub fn tail(args: Vec<Object>) -> Result<Object, RuntimeError> {
     if args.len() != 1 {
-        return Object::ERROR(format!("Expected 1 argument but received {}", args.len()));
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArityMismatch,
+            format!("Expected 1 argument but received {}", args.len()),
+        ));
     }
     match &args[0] {
         Object::ARRAY(arr) => {
-            if arr.len()>0{
-                return Object::ARRAY(arr[1..].to_vec());
+            if !arr.is_empty() {
+                return Ok(Object::ARRAY(arr[1..].to_vec()));
             }
-            Object::ERROR(format!("LogicalError: The array has no elements or hasn't been initialized yet."))
-        },
-        _ => Object::ERROR(format!(
-            "SyntaxError: last() function cannot be used for {}. It can only be used for Array type.",
+            Err(RuntimeError::new(
+                RuntimeErrorKind::IndexOutOfBounds,
+                "The array has no elements or hasn't been initialized yet.",
+            ))
+        }
+        _ => Err(type_error(format!(
+            "tail() function cannot be used for {}. It can only be used for Array type.",
             Object::type_of(args[0].clone())
-        )),
+        ))),
     }
 }
 
-fn push(args: Vec<Object>) -> Object {
+fn push(args: Vec<Object>) -> Result<Object, RuntimeError> {
     match &args[0] {
         Object::ARRAY(arr) => {
             let mut new_arr = arr.clone();
             new_arr.push(args[1].clone());
-            Object::ARRAY(new_arr)
+            Ok(Object::ARRAY(new_arr))
         }
-        o => Object::ERROR(format!(
+        o => Err(type_error(format!(
             "argument to `push` must be array. got {}",
             Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn keys(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::HASH(table) => Ok(Object::ARRAY(
+            table.keys().map(HashKey::to_object).collect(),
+        )),
+        o => Err(type_error(format!(
+            "argument to `keys` must be hash. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn values(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::HASH(table) => Ok(Object::ARRAY(table.values().cloned().collect())),
+        o => Err(type_error(format!(
+            "argument to `values` must be hash. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn has(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match (&args[0], HashKey::from_object(&args[1])) {
+        (Object::HASH(table), Some(key)) => Ok(Object::BOOL(table.contains_key(&key))),
+        (Object::HASH(_), None) => Err(type_error(format!(
+            "{} cannot be used as a hash key",
+            Object::type_of(args[1].clone())
+        ))),
+        (o, _) => Err(type_error(format!(
+            "argument to `has` must be hash. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn delete(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match (&args[0], HashKey::from_object(&args[1])) {
+        (Object::HASH(table), Some(key)) => {
+            let mut new_table = table.clone();
+            new_table.remove(&key);
+            Ok(Object::HASH(new_table))
+        }
+        (Object::HASH(_), None) => Err(type_error(format!(
+            "{} cannot be used as a hash key",
+            Object::type_of(args[1].clone())
+        ))),
+        (o, _) => Err(type_error(format!(
+            "argument to `delete` must be hash. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn merge(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match (&args[0], &args[1]) {
+        (Object::HASH(left), Object::HASH(right)) => {
+            let mut new_table = left.clone();
+            for (key, value) in right.iter() {
+                new_table.insert(key.clone(), value.clone());
+            }
+            Ok(Object::HASH(new_table))
+        }
+        (left, right) => Err(type_error(format!(
+            "arguments to `merge` must be hashes. got {} and {}",
+            Object::type_of(left.clone()),
+            Object::type_of(right.clone())
+        ))),
+    }
+}
+
+/// Converts an already rounded float into an integer, failing for NaN and infinities.
+fn float_to_int(value: f64, name: &str) -> Result<Object, RuntimeError> {
+    match BigInt::from_f64(value) {
+        Some(value) => Ok(Object::from(value)),
+        None => Err(RuntimeError::new(
+            RuntimeErrorKind::InvalidValue,
+            format!(
+                "`{}` cannot convert {} to an integer",
+                name,
+                Object::FLOAT(value)
+            ),
         )),
     }
 }
+
+fn int(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::INTEGER(_) | Object::BIGINT(_) => Ok(args[0].clone()),
+        Object::FLOAT(value) => float_to_int(value.trunc(), "int"),
+        Object::BOOL(value) => Ok(Object::INTEGER(*value as i64)),
+        Object::STRING(value) => match BigInt::parse(value.trim()) {
+            Some(value) => Ok(Object::from(value)),
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidValue,
+                format!("`int` cannot parse \"{}\" as an integer", value),
+            )),
+        },
+        o => Err(type_error(format!(
+            "argument to `int` must be a number, bool or string. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn float(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::INTEGER(value) => Ok(Object::FLOAT(*value as f64)),
+        Object::BIGINT(value) => Ok(Object::FLOAT(value.to_f64())),
+        Object::FLOAT(value) => Ok(Object::FLOAT(*value)),
+        Object::STRING(value) => match value.trim().parse::<f64>() {
+            Ok(value) => Ok(Object::FLOAT(value)),
+            Err(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidValue,
+                format!("`float` cannot parse \"{}\" as a float", value),
+            )),
+        },
+        o => Err(type_error(format!(
+            "argument to `float` must be a number or string. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+/// `round(x)` rounds half away from zero to an integer, while `round(x, digits)`
+/// rounds to the given number of decimal places and keeps the result a float.
+/// Rounds `value` to a multiple of `10 ** places`, with halves rounded away
+/// from zero like `f64::round`.
+fn round_to_power_of_ten(value: &BigInt, places: u64) -> BigInt {
+    // `value` is below `2 ** bits`, which is less than half of `10 ** (bits / 3 + 1)`,
+    // so anything past that rounds to zero without computing the power.
+    if places > value.bits() as u64 / 3 {
+        return BigInt::default();
+    }
+    let scale = BigInt::from_i64(10).pow(places);
+    let (quotient, remainder) = value.div_rem(&scale).unwrap_or_default();
+    let twice = remainder.add(&remainder);
+    let twice = if twice.is_negative() {
+        twice.neg()
+    } else {
+        twice
+    };
+    let quotient = match twice >= scale {
+        true if value.is_negative() => quotient.sub(&BigInt::from_i64(1)),
+        true => quotient.add(&BigInt::from_i64(1)),
+        false => quotient,
+    };
+    quotient.mul(&scale)
+}
+
+fn round(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArityMismatch,
+            format!("Expected 1 or 2 arguments but received {}", args.len()),
+        ));
+    }
+    match (&args[0], args.get(1)) {
+        (Object::INTEGER(_) | Object::BIGINT(_), None) => Ok(args[0].clone()),
+        (Object::FLOAT(value), None) => float_to_int(value.round(), "round"),
+        (Object::INTEGER(value), Some(Object::INTEGER(digits))) if *digits < 0 => Ok(Object::from(
+            round_to_power_of_ten(&BigInt::from_i64(*value), digits.unsigned_abs()),
+        )),
+        (Object::BIGINT(value), Some(Object::INTEGER(digits))) if *digits < 0 => Ok(Object::from(
+            round_to_power_of_ten(value, digits.unsigned_abs()),
+        )),
+        (Object::INTEGER(_) | Object::BIGINT(_), Some(Object::INTEGER(_))) => Ok(args[0].clone()),
+        // A float has fewer than 16 significant digits, so rounding to 15 or
+        // more decimal places leaves it as it is.
+        (Object::FLOAT(value), Some(Object::INTEGER(digits))) if *digits >= 15 => {
+            Ok(Object::FLOAT(*value))
+        }
+        (Object::FLOAT(value), Some(Object::INTEGER(digits))) => {
+            let scale = 10f64.powi((*digits).max(-308) as i32);
+            match value * scale {
+                scaled if scaled.is_finite() => Ok(Object::FLOAT(scaled.round() / scale)),
+                _ => Ok(Object::FLOAT(*value)),
+            }
+        }
+        (_, Some(o)) if !matches!(o, Object::INTEGER(_)) => Err(type_error(format!(
+            "number of digits passed to `round` must be Int. got {}",
+            Object::type_of(o.clone())
+        ))),
+        (o, _) => Err(type_error(format!(
+            "argument to `round` must be a number. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn floor(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::INTEGER(_) | Object::BIGINT(_) => Ok(args[0].clone()),
+        Object::FLOAT(value) => float_to_int(value.floor(), "floor"),
+        o => Err(type_error(format!(
+            "argument to `floor` must be a number. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+fn ceil(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::INTEGER(_) | Object::BIGINT(_) => Ok(args[0].clone()),
+        Object::FLOAT(value) => float_to_int(value.ceil(), "ceil"),
+        o => Err(type_error(format!(
+            "argument to `ceil` must be a number. got {}",
+            Object::type_of(o.clone())
+        ))),
+    }
+}
+
+/// Prints each argument on its own line.
+fn putln(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    for arg in args.iter() {
+        println!("{}", arg.stringify());
+    }
+    Ok(Object::NIL)
+}
+
+/// Writes a value the way it would be written in source, e.g. strings in quotes.
+fn repr(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(Object::STRING(args[0].to_string()))
+}
+
+/// Converts a value to a string: strings are returned as they are and anything
+/// else as `repr` would write it.
+fn str(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(Object::STRING(args[0].stringify()))
+}
+
+/// Returns the `///` comment a function was declared with, or nil when it has none.
+/// The evaluator answers `doc(name)` itself when the binding `name` has a comment.
+fn doc(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match &args[0] {
+        Object::FUNCTION(_, _, _, Some(doc)) => Ok(Object::STRING(doc.clone())),
+        _ => Ok(Object::NIL),
+    }
+}
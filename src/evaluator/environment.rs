@@ -6,6 +6,8 @@ use std::rc::Rc;
 #[derive(PartialEq, Clone, Debug)]
 pub struct Environment {
     store: HashMap<String, Object>,
+    /// The `///` comments of the bindings in `store` that were declared with one.
+    docs: HashMap<String, String>,
     outer: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -13,17 +15,23 @@ impl Environment {
     pub fn new() -> Self {
         Environment {
             store: HashMap::new(),
+            docs: HashMap::new(),
             outer: None,
         }
     }
 
     pub fn from(store: HashMap<String, Object>) -> Self {
-        Environment { store, outer: None }
+        Environment {
+            store,
+            docs: HashMap::new(),
+            outer: None,
+        }
     }
 
     pub fn new_with_outer(outer: Rc<RefCell<Environment>>) -> Self {
         Environment {
             store: HashMap::new(),
+            docs: HashMap::new(),
             outer: Some(outer),
         }
     }
@@ -38,7 +46,53 @@ impl Environment {
         }
     }
 
+    /// Updates an existing binding in the innermost scope that declares `name`.
+    /// Returns `false` when no enclosing scope declares it.
+    pub fn assign(&mut self, name: &str, value: &Object) -> bool {
+        match self.store.get_mut(name) {
+            Some(slot) => {
+                *slot = value.clone();
+                true
+            }
+            None => match self.outer {
+                Some(ref outer) => outer.borrow_mut().assign(name, value),
+                None => false,
+            },
+        }
+    }
+
+    /// Every name visible from this scope, including those of enclosing scopes,
+    /// sorted and without duplicates.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.store.keys().cloned().collect::<Vec<_>>();
+        if let Some(ref outer) = self.outer {
+            names.extend(outer.borrow().names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
     pub fn set(&mut self, name: String, value: &Object) {
         self.store.insert(name, value.clone());
     }
+
+    /// Sets or clears the doc comment of the binding `name` in this scope.
+    pub fn set_doc(&mut self, name: &str, doc: Option<&String>) {
+        match doc {
+            Some(doc) => self.docs.insert(name.to_string(), doc.clone()),
+            None => self.docs.remove(name),
+        };
+    }
+
+    /// The doc comment of the binding `name` refers to, if it was declared with one.
+    pub fn doc(&self, name: &str) -> Option<String> {
+        if self.store.contains_key(name) {
+            return self.docs.get(name).cloned();
+        }
+        match self.outer {
+            Some(ref outer) => outer.borrow().doc(name),
+            None => None,
+        }
+    }
 }
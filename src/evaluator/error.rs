@@ -0,0 +1,90 @@
+use std::fmt::Display;
+
+use crate::tkn::Span;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RuntimeErrorKind {
+    UndefinedName,
+    TypeMismatch,
+    DivisionByZero,
+    IndexOutOfBounds,
+    Overflow,
+    InvalidValue,
+    ArityMismatch,
+    NotCallable,
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RuntimeErrorKind::UndefinedName => "NameError",
+            RuntimeErrorKind::TypeMismatch => "TypeError",
+            RuntimeErrorKind::DivisionByZero => "ZeroDivisionError",
+            RuntimeErrorKind::IndexOutOfBounds => "IndexError",
+            RuntimeErrorKind::Overflow => "OverflowError",
+            RuntimeErrorKind::InvalidValue => "ValueError",
+            RuntimeErrorKind::ArityMismatch => "ArgumentError",
+            RuntimeErrorKind::NotCallable => "TypeError",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A function call that was active when an error was raised.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Frame {
+    /// The name the function was called through, or `<anonymous>`.
+    pub name: String,
+    pub call_site: Span,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub message: String,
+    /// Where the error was raised, once known.
+    pub span: Option<Span>,
+    /// The calls the error unwound through, innermost first.
+    pub stack: Vec<Frame>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            span: None,
+            stack: vec![],
+        }
+    }
+
+    /// Records `span` as the error's location unless a more precise one is already set.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Renders the error the way Python does, outermost call first.
+    pub fn traceback(&self) -> String {
+        let mut lines = vec![String::from("Traceback (most recent call last):")];
+        let mut caller = "<module>";
+        for frame in self.stack.iter().rev() {
+            lines.push(format!("  {}, in {}", frame.call_site, caller));
+            caller = &frame.name;
+        }
+        if let Some(span) = self.span {
+            lines.push(format!("  {}, in {}", span, caller));
+        }
+        lines.push(format!("{}: {}", self.kind, self.message));
+        lines.join("\n")
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{}: {}: {}", span, self.kind, self.message),
+            None => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use super::{BigInt, Object};
+
+/// The subset of objects that can be used as keys of a `HASH`.
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
+pub enum HashKey {
+    Integer(i64),
+    BigInt(BigInt),
+    String(String),
+    Bool(bool),
+}
+
+impl HashKey {
+    pub fn from_object(object: &Object) -> Option<HashKey> {
+        match object {
+            Object::INTEGER(value) => Some(HashKey::Integer(*value)),
+            Object::BIGINT(value) => Some(HashKey::BigInt(value.clone())),
+            Object::STRING(value) => Some(HashKey::String(value.clone())),
+            Object::BOOL(value) => Some(HashKey::Bool(*value)),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            HashKey::Integer(value) => Object::INTEGER(*value),
+            HashKey::BigInt(value) => Object::BIGINT(value.clone()),
+            HashKey::String(value) => Object::STRING(value.clone()),
+            HashKey::Bool(value) => Object::BOOL(*value),
+        }
+    }
+}
+
+/// A hash map that remembers the order in which keys were first inserted.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct HashTable {
+    entries: Vec<(HashKey, Object)>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl HashTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &HashKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Inserts `value` under `key`. Overwriting an existing key keeps its position.
+    pub fn insert(&mut self, key: HashKey, value: Object) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<Object> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (key, _) in self.entries[i..].iter() {
+            if let Some(position) = self.index.get_mut(key) {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(HashKey, Object)> {
+        self.entries.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &HashKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
@@ -1,19 +1,46 @@
+mod bigint;
 pub mod builltin_funcs;
 mod environment;
+mod error;
+mod hash_table;
 mod types;
 
 use std::{cell::RefCell, rc::Rc};
 
-use crate::abstract_tree::{
-    BlockOfStatements, Expression, Identifier, Infix, Literal, Prefix, Program, Statement,
+use crate::{
+    abstract_tree::{
+        BlockOfStatements, Expression, Identifier, Infix, Literal, Prefix, Program, Statement,
+    },
+    tkn::Span,
 };
+pub use bigint::BigInt;
 pub use environment::Environment;
+pub use error::{Frame, RuntimeError, RuntimeErrorKind};
+pub use hash_table::{HashKey, HashTable};
 pub use types::Object;
 
+pub type EvalResult = Result<Object, RuntimeError>;
+
 const TRUE_OBJECT: Object = Object::BOOL(true);
 const FALSE_OBJECT: Object = Object::BOOL(false);
 const NULL_OBJECT: Object = Object::NIL;
 
+/// Upper bound on the size of a big integer produced by `**` or `<<`, so that a
+/// typo like `10 ** 10 ** 10` fails quickly instead of exhausting memory.
+const MAX_INTEGER_BITS: usize = 1 << 20;
+
+/// Evaluates `$expr` to a value. A `return`, `break` or `continue` reached while
+/// evaluating it, as in `let x = if (done) { break } else { 1 }`, is handed straight
+/// back to the caller so that it unwinds to the enclosing call or loop.
+macro_rules! value_of {
+    ($self:ident, $expr:expr) => {
+        match $self.evaluate_expression($expr)? {
+            obj @ (Object::RETURN(_) | Object::BREAK | Object::CONTINUE) => return Ok(obj),
+            obj => obj,
+        }
+    };
+}
+
 #[derive(Debug)]
 pub struct Evaluator {
     env: Rc<RefCell<Environment>>,
@@ -26,463 +53,992 @@ impl Evaluator {
 
     pub fn is_truthy(obj: &Object) -> bool {
         match obj {
-            Object::BOOL(val) => !!val,
+            Object::BOOL(val) => *val,
             Object::NIL => false,
             _ => false,
         }
     }
 
-    pub fn is_error(obj: &Object) -> bool {
-        match obj {
-            Object::ERROR(_) => true,
-            _ => false,
-        }
-    }
-
-    pub fn evaluate(&mut self, program: Program) -> Option<Object> {
-        let mut result = None;
+    /// Evaluates `program`, producing the value of its last statement. Statements
+    /// without a value, like `let`, produce `NIL`.
+    pub fn evaluate(&mut self, program: Program) -> EvalResult {
+        let mut result = NULL_OBJECT;
         for statement in program.iter() {
-            match self.evaluate_statement(statement) {
-                Some(Object::RETURN(val)) => return Some(*val),
-                Some(Object::ERROR(msg)) => return Some(Object::ERROR(msg)),
+            match self.evaluate_statement(statement)? {
+                Object::RETURN(val) => return Ok(*val),
                 obj => result = obj,
             }
         }
-        result
+        Ok(result)
     }
 
-    pub fn evaluate_statement(&mut self, statement: &Statement) -> Option<Object> {
+    pub fn evaluate_statement(&mut self, statement: &Statement) -> EvalResult {
         match statement {
-            Statement::Let { name, value } => {
-                let value = match self.evaluate_expression(value) {
-                    Some(obj) => obj,
-                    None => return None,
-                };
-
-                if Evaluator::is_error(&value) {
-                    Some(value)
-                } else {
-                    let Identifier { literal, token: _ } = name;
-                    self.env.borrow_mut().set(literal.clone(), &value);
-                    // Some(value)
-                    None
+            Statement::Let {
+                name, value, doc, ..
+            } => {
+                let mut value = value_of!(self, value);
+                if let (Object::FUNCTION(_, _, _, slot), Some(doc)) = (&mut value, doc) {
+                    *slot = Some(doc.clone());
                 }
+                let Identifier { literal, token: _ } = name;
+                let mut env = self.env.borrow_mut();
+                env.set(literal.clone(), &value);
+                env.set_doc(literal, doc.as_ref());
+                Ok(NULL_OBJECT)
             }
 
-            Statement::Return { return_value } => {
-                let val = match self.evaluate_expression(return_value) {
-                    Some(val) => val,
-                    None => Object::ERROR(format!(
-                        "EvaluationError: Could not evaluate the return expression \n --> {:?}",
-                        return_value
-                    )),
+            Statement::Return { return_value, .. } => {
+                let val = value_of!(self, return_value);
+                Ok(Object::RETURN(Box::new(val)))
+            }
+            Statement::Expression { expression, .. } => self.evaluate_expression(expression),
+            Statement::While {
+                condition, body, ..
+            } => {
+                loop {
+                    let condition_object = value_of!(self, condition);
+                    if !Evaluator::is_truthy(&condition_object) {
+                        break;
+                    }
+                    match self.evaluate_block_statement(body)? {
+                        Object::BREAK => break,
+                        obj @ Object::RETURN(_) => return Ok(obj),
+                        _ => {}
+                    }
+                }
+                Ok(NULL_OBJECT)
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                let items = match value_of!(self, iterable) {
+                    Object::ARRAY(items) => items,
+                    Object::STRING(str) => str
+                        .chars()
+                        .map(|ch| Object::STRING(ch.to_string()))
+                        .collect(),
+                    Object::HASH(table) => table.keys().map(HashKey::to_object).collect(),
+                    obj => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!("{} is not iterable", Object::type_of(obj)),
+                        )
+                        .at(iterable.span()))
+                    }
                 };
-
-                if Evaluator::is_error(&val) {
-                    return Some(val);
+                for item in items {
+                    self.env.borrow_mut().set(variable.literal.clone(), &item);
+                    match self.evaluate_block_statement(body)? {
+                        Object::BREAK => break,
+                        obj @ Object::RETURN(_) => return Ok(obj),
+                        _ => {}
+                    }
                 }
-
-                Some(Object::RETURN(Box::new(val)))
+                Ok(NULL_OBJECT)
             }
-            Statement::Expression { expression } => self.evaluate_expression(expression),
+            Statement::Assign {
+                target,
+                operator,
+                value,
+                ..
+            } => {
+                let value = value_of!(self, value);
+                self.evaluate_assignment(target, operator.as_ref(), value)
+            }
+            Statement::Break(_) => Ok(Object::BREAK),
+            Statement::Continue(_) => Ok(Object::CONTINUE),
         }
     }
 
-    pub fn evaluate_expression(&mut self, expression: &Expression) -> Option<Object> {
+    pub fn evaluate_expression(&mut self, expression: &Expression) -> EvalResult {
         match expression {
-            Expression::Identifier(ident) => match self.env.borrow_mut().get(ident.literal.clone()) {
-                Some(obj) => Some(obj),
-                None => {
-                    Some(Object::ERROR(format!(
-                    "EvaluationError: The identifier {} has not been declared yet and hence is illegal.",
-                    ident.literal
-                    )))
+            Expression::Identifier(ident) => match self.env.borrow_mut().get(ident.literal.clone())
+            {
+                Some(obj) => Ok(obj),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedName,
+                    format!(
+                        "The identifier {} has not been declared yet and hence is illegal.",
+                        ident.literal
+                    ),
+                )
+                .at(ident.span())),
             },
-            },
-            Expression::Literal(literal) => self.evaluate_literal(literal.clone()),
-            Expression::Prefix(prefix, right) => {
-                let right = self.evaluate_expression(right);
-                if right.is_none() {
-                    return Some(NULL_OBJECT);
-                }
-                let right = right.unwrap();
-                if Evaluator::is_error(&right) {
-                    return Some(right);
-                }
+            Expression::Literal(literal, _) => self.evaluate_literal(literal.clone()),
+            Expression::Prefix(prefix, right, span) => {
+                let right = value_of!(self, right);
                 self.evaluate_prefix(prefix.clone(), right)
+                    .map_err(|error| error.at(*span))
             }
-            Expression::Infix(operator, left, right) => {
-                let left = self.evaluate_expression(left);
-                if left.is_none() {
-                    return Some(NULL_OBJECT);
-                }
-                let left = left.unwrap();
-                if Evaluator::is_error(&left) {
-                    return Some(left);
+            Expression::Infix(operator, left, right, span) => {
+                let left = value_of!(self, left);
+                // `&&` and `||` only evaluate their right side when the left one doesn't decide the result.
+                match operator {
+                    Infix::And if !Evaluator::is_truthy(&left) => return Ok(FALSE_OBJECT),
+                    Infix::Or if Evaluator::is_truthy(&left) => return Ok(TRUE_OBJECT),
+                    _ => {}
                 }
-                let right = self.evaluate_expression(right);
-                if right.is_none() {
-                    return Some(NULL_OBJECT);
-                }
-                let right = right.unwrap();
-                if Evaluator::is_error(&right) {
-                    return Some(right);
+                let right = value_of!(self, right);
+                if let Infix::And | Infix::Or = operator {
+                    return Ok(Object::BOOL(Evaluator::is_truthy(&right)));
                 }
                 self.evaluate_infix(operator.clone(), left, right)
+                    .map_err(|error| error.at(*span))
             }
             Expression::If {
                 condition,
                 consequence,
                 alternative,
+                ..
             } => {
-                let condition_object = self.evaluate_expression(&condition);
-                if condition_object.is_none() {
-                    return Some(Object::ERROR(format!(
-                        "EvaluationError: Could not evaluate the given condition\n --> {:?}",
-                        condition
-                    )));
-                }
-                let condition_object = condition_object.unwrap();
-                if Evaluator::is_error(&condition_object) {
-                    return Some(condition_object);
-                }
+                let condition_object = value_of!(self, condition);
                 if Evaluator::is_truthy(&condition_object) {
                     self.evaluate_block_statement(consequence)
                 } else if let Some(alt) = alternative {
                     self.evaluate_block_statement(alt)
                 } else {
-                    None
+                    Ok(NULL_OBJECT)
                 }
             }
-            // Expression::Function { params, body} => Some(Object::FUNCTION(params.clone(), body.clone(), Rc::new(RefCell::new(Environment::new()))))
-            Expression::Function { params, body} => Some(Object::FUNCTION(params.clone(), body.clone(), Rc::clone(&self.env)))
-            ,
-            Expression::Call { func, args } => Some(self.evaluate_call_expr(func.clone(),(args.clone().unwrap()).to_vec())),
-            Expression::Index(left_expr, index_expr) => {
-                let left =  self.evaluate_expression(left_expr);
-                let index_expr = self.evaluate_expression(index_expr);
-                if left.is_some() && index_expr.is_some(){
-                self.eval_index_expr(left.unwrap(),index_expr.unwrap())
-                }else{
-                    None
+            Expression::Interpolation(parts, _) => {
+                let mut string = String::new();
+                for part in parts {
+                    string.push_str(&value_of!(self, part).stringify());
                 }
-            },
+                Ok(Object::STRING(string))
+            }
+            Expression::Function { params, body, .. } => Ok(Object::FUNCTION(
+                params.clone(),
+                body.clone(),
+                Rc::clone(&self.env),
+                None,
+            )),
+            Expression::Call { func, args, span } => {
+                self.evaluate_call_expr(func, args.as_deref().unwrap_or_default(), *span)
+            }
+            Expression::Index(left_expr, index_expr, span) => {
+                let left = value_of!(self, left_expr);
+                let index = value_of!(self, index_expr);
+                self.eval_index_expr(left, index)
+                    .map_err(|error| error.at(*span))
+            }
+        }
+    }
+
+    /// Assigns `value` to a name or to an element reached by indexing into one.
+    ///
+    /// Arrays and hashes have value semantics: updating an element rebuilds the
+    /// container and rebinds the name at the root of `target`, so other names that
+    /// were bound to a copy of the container don't observe the change.
+    fn evaluate_assignment(
+        &mut self,
+        target: &Expression,
+        operator: Option<&Infix>,
+        value: Object,
+    ) -> EvalResult {
+        let mut index_exprs = vec![];
+        let mut root = target;
+        while let Expression::Index(left, index, _) = root {
+            index_exprs.push(index.as_ref());
+            root = left;
+        }
+        index_exprs.reverse();
 
+        let Expression::Identifier(ident) = root else {
+            unreachable!(
+                "the parser only accepts names and index expressions as assignment targets"
+            )
+        };
+        let current = match self.env.borrow_mut().get(ident.literal.clone()) {
+            Some(current) => current,
+            None => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedName,
+                    format!(
+                        "Cannot assign to {0} because it has not been declared yet. Declare it first with `let {0} = ...`.",
+                        ident.literal
+                    ),
+                )
+                .at(ident.span()))
+            }
+        };
+        let mut indices = Vec::with_capacity(index_exprs.len());
+        for expr in index_exprs {
+            indices.push(value_of!(self, expr));
         }
+
+        let updated = self
+            .assign_element(current, &indices, operator, value)
+            .map_err(|error| error.at(target.span()))?;
+        self.env.borrow_mut().assign(&ident.literal, &updated);
+        Ok(NULL_OBJECT)
     }
 
-    fn eval_index_expr(&mut self, left: Object, index_expr: Object) -> Option<Object> {
+    /// Returns `container` with the element found by following `indices` replaced
+    /// by `value`, or combined with it through `operator` for compound assignments.
+    fn assign_element(
+        &mut self,
+        container: Object,
+        indices: &[Object],
+        operator: Option<&Infix>,
+        value: Object,
+    ) -> EvalResult {
+        let Some((index, rest)) = indices.split_first() else {
+            return match operator {
+                Some(operator) => self.evaluate_infix(operator.clone(), container, value),
+                None => Ok(value),
+            };
+        };
+
+        match container {
+            Object::ARRAY(mut items) => {
+                let position = match index {
+                    Object::INTEGER(i) if (0..items.len() as i64).contains(i) => *i as usize,
+                    Object::INTEGER(i) => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::IndexOutOfBounds,
+                            format!(
+                                "Array index {} is out of bounds for an array of length {}",
+                                i,
+                                items.len()
+                            ),
+                        ))
+                    }
+                    _ => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "{} cannot be used as an array index",
+                                Object::type_of(index.clone())
+                            ),
+                        ))
+                    }
+                };
+                let element = std::mem::replace(&mut items[position], NULL_OBJECT);
+                items[position] = self.assign_element(element, rest, operator, value)?;
+                Ok(Object::ARRAY(items))
+            }
+            Object::HASH(mut table) => {
+                let key = match HashKey::from_object(index) {
+                    Some(key) => key,
+                    None => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "{} cannot be used as a hash key",
+                                Object::type_of(index.clone())
+                            ),
+                        ))
+                    }
+                };
+                let element = table.get(&key).cloned().unwrap_or(NULL_OBJECT);
+                let element = self.assign_element(element, rest, operator, value)?;
+                table.insert(key, element);
+                Ok(Object::HASH(table))
+            }
+            o => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!("Cannot assign into an element of a {}", Object::type_of(o)),
+            )),
+        }
+    }
+
+    fn eval_index_expr(&mut self, left: Object, index_expr: Object) -> EvalResult {
         match left {
             Object::ARRAY(ref array) => {
                 if let Object::INTEGER(i) = index_expr {
                     let length = array.len() as i64;
                     if i < 0 || i >= length {
-                        return Some(Object::ERROR(format!(
-                            "EvaluationError: Array index out of bounds",
-                        )));
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::IndexOutOfBounds,
+                            format!(
+                                "Array index {} is out of bounds for an array of length {}",
+                                i, length
+                            ),
+                        ));
                     }
 
                     match array.get(i as usize) {
-                        Some(o) => Some(o.clone()),
-                        None => Some(NULL_OBJECT),
+                        Some(o) => Ok(o.clone()),
+                        None => Ok(NULL_OBJECT),
                     }
                 } else {
-                    return Some(Object::ERROR(format!(
-                        "EvaluationError: {:?} cannot be used as an array index",
-                        index_expr
-                    )));
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{} cannot be used as an array index",
+                            Object::type_of(index_expr)
+                        ),
+                    ))
                 }
             }
-            o => Some(Object::ERROR(format!(
-                "EvaluationError: Cannot index a  {}",
-                Object::type_of(o)
-            ))),
-        }
-    }
-    // fn evaluate_expressions(&mut self, expressions: &Vec<Expression>) -> Option<Vec<Object>> {
-    //     let mut result = Vec::<Object>::new();
-
-    //     for expr in expressions {
-    //         let eval = self.evaluate_expression(expr);
-    //         if eval.is_none() {
-    //             return Some(vec![Object::ERROR(format!(
-    //                 "EvaluationError: Couldn't evaluate the expression --> {:?}",
-    //                 expr
-    //             ))]);
-    //         }
-    //         result.push(match eval.unwrap() {
-    //             Object::ERROR(error_msg) => return Some(vec![Object::ERROR(error_msg)]),
-    //             obj => obj,
-    //         });
-    //     }
-
-    //     Some(result)
-    // }
-
-    fn evaluate_literal(&mut self, literal: Literal) -> Option<Object> {
-        match literal {
-            Literal::Int { token: _, value } => {
-                return Some(Object::INTEGER(value));
+            // Strings are indexed by character, not by byte.
+            Object::STRING(ref string) => {
+                if let Object::INTEGER(i) = index_expr {
+                    let ch = usize::try_from(i).ok().and_then(|i| string.chars().nth(i));
+                    match ch {
+                        Some(ch) => Ok(Object::STRING(ch.to_string())),
+                        None => Err(RuntimeError::new(
+                            RuntimeErrorKind::IndexOutOfBounds,
+                            format!(
+                                "String index {} is out of bounds for a string of length {}",
+                                i,
+                                string.chars().count()
+                            ),
+                        )),
+                    }
+                } else {
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{} cannot be used as a string index",
+                            Object::type_of(index_expr)
+                        ),
+                    ))
+                }
             }
-            Literal::String(str) => return Some(Object::STRING(str)),
+            Object::HASH(ref table) => match HashKey::from_object(&index_expr) {
+                Some(key) => Ok(table.get(&key).cloned().unwrap_or(NULL_OBJECT)),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::TypeMismatch,
+                    format!(
+                        "{} cannot be used as a hash key",
+                        Object::type_of(index_expr)
+                    ),
+                )),
+            },
+            o => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!("Cannot index a {}", Object::type_of(o)),
+            )),
+        }
+    }
+    fn evaluate_literal(&mut self, literal: Literal) -> EvalResult {
+        match literal {
+            Literal::Int { token: _, value } => Ok(Object::INTEGER(value)),
+            Literal::BigInt { token: _, value } => Ok(Object::from(value)),
+            Literal::Float { token: _, value } => Ok(Object::FLOAT(value)),
+            Literal::String { token: _, value } => Ok(Object::STRING(value)),
             Literal::Bool(bool) => match bool {
-                true => Some(TRUE_OBJECT),
-                false => Some(FALSE_OBJECT),
+                true => Ok(TRUE_OBJECT),
+                false => Ok(FALSE_OBJECT),
             },
             Literal::Array(objects) => self.evaluate_array_literal(objects),
-            Literal::Hash(_) => Some(NULL_OBJECT),
+            Literal::Hash(pairs) => self.evaluate_hash_literal(pairs),
         }
     }
 
-    fn evaluate_array_literal(&mut self, objects: Vec<Expression>) -> Option<Object> {
-        Some(Object::ARRAY(
-            objects
-                .iter()
-                .map(|expr| self.evaluate_expression(expr).unwrap_or(NULL_OBJECT))
-                .collect::<Vec<_>>(),
-        ))
+    fn evaluate_hash_literal(&mut self, pairs: Vec<(Expression, Expression)>) -> EvalResult {
+        let mut table = HashTable::new();
+        for (key_expr, value_expr) in pairs.iter() {
+            let key = value_of!(self, key_expr);
+            let key = match HashKey::from_object(&key) {
+                Some(key) => key,
+                None => {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!("{} cannot be used as a hash key", Object::type_of(key)),
+                    )
+                    .at(key_expr.span()))
+                }
+            };
+
+            let value = value_of!(self, value_expr);
+            table.insert(key, value);
+        }
+        Ok(Object::HASH(table))
+    }
+
+    fn evaluate_array_literal(&mut self, objects: Vec<Expression>) -> EvalResult {
+        let mut items = Vec::with_capacity(objects.len());
+        for expr in objects.iter() {
+            items.push(value_of!(self, expr));
+        }
+        Ok(Object::ARRAY(items))
     }
 
-    fn evaluate_prefix(&mut self, prefix: Prefix, expression: Object) -> Option<Object> {
+    pub fn evaluate_prefix(&mut self, prefix: Prefix, expression: Object) -> EvalResult {
         match prefix {
-            Prefix::Plus => Some(NULL_OBJECT),
+            Prefix::Plus => match expression {
+                Object::INTEGER(_) | Object::BIGINT(_) | Object::FLOAT(_) => Ok(expression),
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::TypeMismatch,
+                    format!(
+                        "PLUS operator cannot be used for the type {}",
+                        Object::type_of(expression)
+                    ),
+                )),
+            },
             Prefix::Minus => self.evaluate_minus_operator_expression(expression),
             Prefix::Not => self.evaluate_not_operator_expression(expression),
         }
     }
-    fn evaluate_infix(&mut self, operator: Infix, left: Object, right: Object) -> Option<Object> {
+    pub fn evaluate_infix(&mut self, operator: Infix, left: Object, right: Object) -> EvalResult {
+        // Mixing an integer with a float promotes the integer to a float.
+        match (&left, &right) {
+            (Object::FLOAT(left_val), Object::FLOAT(right_val)) => {
+                return self.evaluate_float_infix(operator, *left_val, *right_val)
+            }
+            (Object::INTEGER(left_val), Object::FLOAT(right_val)) => {
+                return self.evaluate_float_infix(operator, *left_val as f64, *right_val)
+            }
+            (Object::FLOAT(left_val), Object::INTEGER(right_val)) => {
+                return self.evaluate_float_infix(operator, *left_val, *right_val as f64)
+            }
+            (Object::BIGINT(left_val), Object::FLOAT(right_val)) => {
+                return self.evaluate_float_infix(operator, left_val.to_f64(), *right_val)
+            }
+            (Object::FLOAT(left_val), Object::BIGINT(right_val)) => {
+                return self.evaluate_float_infix(operator, *left_val, right_val.to_f64())
+            }
+            (Object::BIGINT(left_val), Object::BIGINT(right_val)) => {
+                return Self::evaluate_bigint_infix(operator, left_val, right_val)
+            }
+            (Object::BIGINT(left_val), Object::INTEGER(right_val)) => {
+                return Self::evaluate_bigint_infix(
+                    operator,
+                    left_val,
+                    &BigInt::from_i64(*right_val),
+                )
+            }
+            (Object::INTEGER(left_val), Object::BIGINT(right_val)) => {
+                return Self::evaluate_bigint_infix(
+                    operator,
+                    &BigInt::from_i64(*left_val),
+                    right_val,
+                )
+            }
+            _ => {}
+        }
+
         match left {
             Object::INTEGER(left_val) => {
                 if let Object::INTEGER(right_val) = right {
+                    // Results that overflow an i64 are recomputed as big integers.
+                    let promoted = || {
+                        Self::evaluate_bigint_infix(
+                            operator.clone(),
+                            &BigInt::from_i64(left_val),
+                            &BigInt::from_i64(right_val),
+                        )
+                    };
                     match operator {
-                        Infix::Plus => Some(Object::INTEGER(left_val + right_val)),
-                        Infix::Minus => Some(Object::INTEGER(left_val - right_val)),
-                        Infix::Divide => Some(Object::INTEGER(left_val / right_val)),
-                        Infix::Multiply => Some(Object::INTEGER(left_val * right_val)),
+                        Infix::Plus => left_val
+                            .checked_add(right_val)
+                            .map(Object::INTEGER)
+                            .map_or_else(promoted, Ok),
+                        Infix::Minus => left_val
+                            .checked_sub(right_val)
+                            .map(Object::INTEGER)
+                            .map_or_else(promoted, Ok),
+                        Infix::Multiply => left_val
+                            .checked_mul(right_val)
+                            .map(Object::INTEGER)
+                            .map_or_else(promoted, Ok),
+                        Infix::Divide => {
+                            if right_val == 0 {
+                                Err(RuntimeError::new(
+                                    RuntimeErrorKind::DivisionByZero,
+                                    "Division by zero",
+                                ))
+                            } else {
+                                left_val
+                                    .checked_div(right_val)
+                                    .map(Object::INTEGER)
+                                    .map_or_else(promoted, Ok)
+                            }
+                        }
+                        Infix::Modulo => {
+                            if right_val == 0 {
+                                Err(RuntimeError::new(
+                                    RuntimeErrorKind::DivisionByZero,
+                                    "Modulo by zero",
+                                ))
+                            } else {
+                                Ok(Object::INTEGER(left_val.wrapping_rem(right_val)))
+                            }
+                        }
+                        Infix::Power => {
+                            if right_val < 0 {
+                                return Err(RuntimeError::new(
+                                    RuntimeErrorKind::InvalidValue,
+                                    format!(
+                                        "Negative exponent {} is not supported for integers",
+                                        right_val
+                                    ),
+                                ));
+                            }
+                            u32::try_from(right_val)
+                                .ok()
+                                .and_then(|exp| left_val.checked_pow(exp))
+                                .map(Object::INTEGER)
+                                .map_or_else(promoted, Ok)
+                        }
+                        Infix::BitAnd => Ok(Object::INTEGER(left_val & right_val)),
+                        Infix::BitOr => Ok(Object::INTEGER(left_val | right_val)),
+                        Infix::BitXor => Ok(Object::INTEGER(left_val ^ right_val)),
+                        Infix::ShiftLeft => {
+                            if (0..64).contains(&right_val)
+                                && (left_val << right_val) >> right_val == left_val
+                            {
+                                Ok(Object::INTEGER(left_val << right_val))
+                            } else {
+                                promoted()
+                            }
+                        }
+                        Infix::ShiftRight => {
+                            if right_val < 0 {
+                                promoted()
+                            } else {
+                                Ok(Object::INTEGER(left_val >> right_val.min(63)))
+                            }
+                        }
+                        Infix::And | Infix::Or => Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "{:?} Operation not supported between integer objects.",
+                                operator,
+                            ),
+                        )),
                         Infix::Equal => {
                             if left_val == right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::NotEqual => {
                             if left_val != right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::GreaterThanEqual => {
                             if left_val >= right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::GreaterThan => {
                             if left_val > right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::LessThanEqual => {
                             if left_val <= right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::LessThan => {
                             if left_val < right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                     }
                 } else {
-                    return Some(Object::ERROR(format!(
-                        "EvaluationError: {:?} Operator not supported between the two objects {:?} and {:?}",
-                        operator,
-                        Object::type_of(left),
-                        Object::type_of(right)
-                    )));
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{:?} Operator not supported between the two objects {:?} and {:?}",
+                            operator,
+                            Object::type_of(left),
+                            Object::type_of(right)
+                        ),
+                    ))
                 }
             }
             Object::BOOL(left_val) => {
                 if let Object::BOOL(right_val) = right {
                     match operator {
+                        Infix::And => Ok(Object::BOOL(left_val && right_val)),
+                        Infix::Or => Ok(Object::BOOL(left_val || right_val)),
                         Infix::Plus
                         | Infix::Minus
                         | Infix::Divide
                         | Infix::Multiply
+                        | Infix::Modulo
+                        | Infix::Power
+                        | Infix::BitAnd
+                        | Infix::BitOr
+                        | Infix::BitXor
+                        | Infix::ShiftLeft
+                        | Infix::ShiftRight
                         | Infix::GreaterThanEqual
                         | Infix::GreaterThan
                         | Infix::LessThan
-                        | Infix::LessThanEqual => {
-                            return Some(Object::ERROR(format!(
-                                "EvaluationError: {:?} Operation not supported between boolean expresions.",
+                        | Infix::LessThanEqual => Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "{:?} Operation not supported between boolean expresions.",
                                 operator,
-                            )))
-                        }
+                            ),
+                        )),
                         Infix::Equal => {
                             if left_val == right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::NotEqual => {
                             if left_val != right_val {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                     }
                 } else {
-                    return Some(Object::ERROR(format!(
-                        "EvaluationError: {:?} Operation not supported between the two objects {:?} and {:?}",
-                        operator,
-                        Object::type_of(left),
-                        Object::type_of(right)
-                    )));
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{:?} Operation not supported between the two objects {:?} and {:?}",
+                            operator,
+                            Object::type_of(left),
+                            Object::type_of(right)
+                        ),
+                    ))
                 }
             }
-            Object::NIL | Object::ERROR(_) | Object::RETURN(_) | Object::FUNCTION(_, _, _) => {
-                return Some(Object::ERROR(format!(
-                    "EvaluationError: {:?} Operator not supported between the two objects {:?} and {:?}",
+            Object::NIL
+            | Object::BIGINT(_)
+            | Object::FLOAT(_)
+            | Object::RETURN(_)
+            | Object::BREAK
+            | Object::CONTINUE
+            | Object::FUNCTION(..) => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operator not supported between the two objects {:?} and {:?}",
                     operator,
                     Object::type_of(left),
                     Object::type_of(right)
-                )));
-            }
+                ),
+            )),
             Object::STRING(ref left_str) => {
                 if let Object::STRING(ref right_str) = right {
                     match operator {
                         Infix::Plus => {
-                            let concat = left_str.clone() + &right_str;
-                            Some(Object::STRING(concat))
+                            let concat = left_str.clone() + right_str;
+                            Ok(Object::STRING(concat))
                         }
                         Infix::Minus
                         | Infix::Divide
                         | Infix::Multiply
+                        | Infix::Modulo
+                        | Infix::Power
+                        | Infix::And
+                        | Infix::Or
+                        | Infix::BitAnd
+                        | Infix::BitOr
+                        | Infix::BitXor
+                        | Infix::ShiftLeft
+                        | Infix::ShiftRight
                         | Infix::GreaterThanEqual
                         | Infix::GreaterThan
                         | Infix::LessThan
-                        | Infix::LessThanEqual => {
-                            return Some(Object::ERROR(format!(
-                            "EvaluationError: {:?} Operation not supported between string objects.",
-                            operator,
-                        )))
-                        }
+                        | Infix::LessThanEqual => Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "{:?} Operation not supported between string objects.",
+                                operator,
+                            ),
+                        )),
                         Infix::Equal => {
                             if left_str == right_str {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                         Infix::NotEqual => {
                             if left_str != right_str {
-                                Some(TRUE_OBJECT)
+                                Ok(TRUE_OBJECT)
                             } else {
-                                Some(FALSE_OBJECT)
+                                Ok(FALSE_OBJECT)
                             }
                         }
                     }
                 } else {
-                    return Some(Object::ERROR(format!(
-                    "EvaluationError: {:?} Operation not supported between the two objects {:?} and {:?}",
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{:?} Operation not supported between the two objects {:?} and {:?}",
+                            operator,
+                            Object::type_of(left),
+                            Object::type_of(right)
+                        ),
+                    ))
+                }
+            }
+            Object::BUILTIN { .. } => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operation not supported between the two objects {:?} and {:?}",
                     operator,
                     Object::type_of(left),
                     Object::type_of(right)
-                )));
+                ),
+            )),
+            Object::ARRAY(_) | Object::HASH(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operation not supported between the two objects {:?} and {:?}",
+                    operator,
+                    Object::type_of(left),
+                    Object::type_of(right)
+                ),
+            )),
+        }
+    }
+
+    /// Integer arithmetic past the range of an i64. Results are demoted back to
+    /// `INTEGER` whenever they fit again.
+    fn evaluate_bigint_infix(operator: Infix, left: &BigInt, right: &BigInt) -> EvalResult {
+        let too_large = || {
+            Err(RuntimeError::new(
+                RuntimeErrorKind::Overflow,
+                format!(
+                    "The result of {} {} {} is too large, integers are limited to {} bits",
+                    left, operator, right, MAX_INTEGER_BITS
+                ),
+            ))
+        };
+        match operator {
+            Infix::Plus => Ok(Object::from(left.add(right))),
+            Infix::Minus => Ok(Object::from(left.sub(right))),
+            Infix::Multiply => Ok(Object::from(left.mul(right))),
+            Infix::Divide => match left.div_rem(right) {
+                Some((quotient, _)) => Ok(Object::from(quotient)),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::DivisionByZero,
+                    "Division by zero",
+                )),
+            },
+            Infix::Modulo => match left.div_rem(right) {
+                Some((_, remainder)) => Ok(Object::from(remainder)),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::DivisionByZero,
+                    "Modulo by zero",
+                )),
+            },
+            Infix::Power => {
+                if right.is_negative() {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::InvalidValue,
+                        format!("Negative exponent {} is not supported for integers", right),
+                    ));
+                }
+                // 0, 1 and -1 stay small whatever the exponent.
+                if left.bits() <= 1 {
+                    let odd = right.div_rem(&BigInt::from_i64(2)).unwrap().1;
+                    return match left.to_i64() {
+                        Some(-1) if odd.is_zero() => Ok(Object::INTEGER(1)),
+                        Some(0) if right.is_zero() => Ok(Object::INTEGER(1)),
+                        Some(value) => Ok(Object::INTEGER(value)),
+                        None => unreachable!(),
+                    };
+                }
+                match right.to_i64() {
+                    Some(exp)
+                        if (left.bits() as u128) * (exp as u128) <= MAX_INTEGER_BITS as u128 =>
+                    {
+                        Ok(Object::from(left.pow(exp as u64)))
+                    }
+                    _ => too_large(),
                 }
             }
-            Object::BUILTIN { arity:_, builtInFunc:_ }=> Some(Object::ERROR(format!(
-                "EvaluationError: {:?} Operation not supported between the two objects {:?} and {:?}",
-                operator,
-                Object::type_of(left),
-                Object::type_of(right)
-            ))),
-            Object::ARRAY(_) => Some(Object::ERROR(format!(
-                "EvaluationError: {:?} Operation not supported between the two objects {:?} and {:?}",
-                operator,
-                Object::type_of(left),
-                Object::type_of(right)
-            ))),
+            Infix::ShiftLeft | Infix::ShiftRight => {
+                if right.is_negative() {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::InvalidValue,
+                        format!("Negative shift amount {}", right),
+                    ));
+                }
+                let amount = right.to_i64().map_or(usize::MAX, |amount| amount as usize);
+                if operator == Infix::ShiftRight {
+                    Ok(Object::from(left.shr(amount.min(left.bits() + 1))))
+                } else if left.is_zero() {
+                    Ok(Object::INTEGER(0))
+                } else if amount > MAX_INTEGER_BITS - left.bits() {
+                    too_large()
+                } else {
+                    Ok(Object::from(left.shl(amount)))
+                }
+            }
+            Infix::BitAnd | Infix::BitOr | Infix::BitXor => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operation is only supported for integers that fit in 64 bits.",
+                    operator,
+                ),
+            )),
+            Infix::And | Infix::Or => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operation not supported between integer objects.",
+                    operator,
+                ),
+            )),
+            Infix::Equal => Ok(Object::BOOL(left == right)),
+            Infix::NotEqual => Ok(Object::BOOL(left != right)),
+            Infix::GreaterThanEqual => Ok(Object::BOOL(left >= right)),
+            Infix::GreaterThan => Ok(Object::BOOL(left > right)),
+            Infix::LessThanEqual => Ok(Object::BOOL(left <= right)),
+            Infix::LessThan => Ok(Object::BOOL(left < right)),
+        }
+    }
+
+    fn evaluate_float_infix(
+        &mut self,
+        operator: Infix,
+        left_val: f64,
+        right_val: f64,
+    ) -> EvalResult {
+        match operator {
+            Infix::Plus => Ok(Object::FLOAT(left_val + right_val)),
+            Infix::Minus => Ok(Object::FLOAT(left_val - right_val)),
+            Infix::Divide => Ok(Object::FLOAT(left_val / right_val)),
+            Infix::Multiply => Ok(Object::FLOAT(left_val * right_val)),
+            Infix::Modulo => Ok(Object::FLOAT(left_val % right_val)),
+            Infix::Power => Ok(Object::FLOAT(left_val.powf(right_val))),
+            Infix::Equal => Ok(Object::BOOL(left_val == right_val)),
+            Infix::NotEqual => Ok(Object::BOOL(left_val != right_val)),
+            Infix::GreaterThanEqual => Ok(Object::BOOL(left_val >= right_val)),
+            Infix::GreaterThan => Ok(Object::BOOL(left_val > right_val)),
+            Infix::LessThanEqual => Ok(Object::BOOL(left_val <= right_val)),
+            Infix::LessThan => Ok(Object::BOOL(left_val < right_val)),
+            Infix::And
+            | Infix::Or
+            | Infix::BitAnd
+            | Infix::BitOr
+            | Infix::BitXor
+            | Infix::ShiftLeft
+            | Infix::ShiftRight => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "{:?} Operation not supported between float objects.",
+                    operator,
+                ),
+            )),
         }
     }
 
-    fn evaluate_not_operator_expression(&mut self, expression: Object) -> Option<Object> {
+    fn evaluate_not_operator_expression(&mut self, expression: Object) -> EvalResult {
         match expression {
-            Object::BOOL(true) => Some(FALSE_OBJECT),
-            Object::BOOL(false) => Some(TRUE_OBJECT),
-            Object::NIL => Some(TRUE_OBJECT),
-            _ => Some(FALSE_OBJECT),
+            Object::BOOL(true) => Ok(FALSE_OBJECT),
+            Object::BOOL(false) => Ok(TRUE_OBJECT),
+            Object::NIL => Ok(TRUE_OBJECT),
+            _ => Ok(FALSE_OBJECT),
         }
     }
 
-    fn evaluate_minus_operator_expression(&mut self, expression: Object) -> Option<Object> {
+    fn evaluate_minus_operator_expression(&mut self, expression: Object) -> EvalResult {
         match expression {
-            Object::INTEGER(val) => Some(Object::INTEGER(-val)),
-            _ => Some(Object::ERROR(format!(
-                "EvaluationError: MINUS operator cannot be used for the type {}",
-                Object::type_of(expression)
-            ))),
+            Object::INTEGER(val) => match val.checked_neg() {
+                Some(val) => Ok(Object::INTEGER(val)),
+                None => Ok(Object::from(BigInt::from_i64(val).neg())),
+            },
+            Object::BIGINT(val) => Ok(Object::from(val.neg())),
+            Object::FLOAT(val) => Ok(Object::FLOAT(-val)),
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "MINUS operator cannot be used for the type {}",
+                    Object::type_of(expression)
+                ),
+            )),
         }
     }
 
-    fn evaluate_block_statement(&mut self, statements: &BlockOfStatements) -> Option<Object> {
-        let mut result = None;
+    fn evaluate_block_statement(&mut self, statements: &BlockOfStatements) -> EvalResult {
+        let mut result = NULL_OBJECT;
         for statement in statements {
-            match self.evaluate_statement(statement) {
-                Some(Object::RETURN(val)) => return Some(*val),
-                Some(Object::ERROR(_)) => return result,
+            // `return`, `break` and `continue` inside a nested block have to unwind
+            // to the enclosing call or loop.
+            match self.evaluate_statement(statement)? {
+                obj @ (Object::RETURN(_) | Object::BREAK | Object::CONTINUE) => return Ok(obj),
                 obj => result = obj,
             }
         }
-        result
+        Ok(result)
     }
 
-    fn evaluate_call_expr(&mut self, func: Box<Expression>, args: Vec<Expression>) -> Object {
-        let args_supplied = args
-            .iter()
-            .map(|e| self.evaluate_expression(e).unwrap_or(NULL_OBJECT))
-            .collect::<Vec<_>>();
+    fn evaluate_call_expr(
+        &mut self,
+        func: &Expression,
+        args: &[Expression],
+        span: Span,
+    ) -> EvalResult {
+        let function = value_of!(self, func);
+        // Values other than functions can't hold a doc comment, so `doc(name)`
+        // also looks at the binding `name` was declared with.
+        if let (Object::BUILTIN { name: "doc", .. }, [Expression::Identifier(ident)]) =
+            (&function, args)
+        {
+            if let Some(doc) = self.env.borrow().doc(&ident.literal) {
+                return Ok(Object::STRING(doc));
+            }
+        }
+        let mut args_supplied = Vec::with_capacity(args.len());
+        for arg in args {
+            args_supplied.push(value_of!(self, arg));
+        }
 
-        let (args_expected, body, env) = match self.evaluate_expression(&func) {
-            Some(Object::FUNCTION(params, body, env)) => (params, body, env),
-            Some(Object::BUILTIN { arity, builtInFunc }) => {
-                if arity < 0 || arity == args_supplied.len() as i16 {
-                    return builtInFunc(args_supplied);
-                } else {
-                    return Object::ERROR(format!(
-                        "EvaluationError: Expected {} arguments, but {} were supplied.",
-                        arity,
-                        args_supplied.len()
-                    ));
+        let (args_expected, body, env) = match function {
+            Object::FUNCTION(params, body, env, _) => (params, body, env),
+            Object::BUILTIN {
+                arity,
+                builtInFunc: builtin_func,
+                ..
+            } => {
+                if arity >= 0 && arity != args_supplied.len() as i16 {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::ArityMismatch,
+                        format!(
+                            "Expected {} arguments, but {} were supplied.",
+                            arity,
+                            args_supplied.len()
+                        ),
+                    )
+                    .at(span));
                 }
+                return builtin_func(args_supplied).map_err(|error| error.at(span));
             }
-
-            Some(obj) => {
-                return Object::ERROR(format!(
-                    "EvaluationError: Expected function instead received {}",
-                    Object::type_of(obj)
-                ))
+            obj => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::NotCallable,
+                    format!(
+                        "Expected function instead received {}",
+                        Object::type_of(obj)
+                    ),
+                )
+                .at(span))
             }
-            None => return NULL_OBJECT,
         };
 
         if args_supplied.len() != args_expected.len() {
-            return Object::ERROR(format!(
-                "EvaluationError: Expected {} arguments, but {} were supplied.",
-                args_expected.len(),
-                args_supplied.len()
-            ));
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::ArityMismatch,
+                format!(
+                    "Expected {} arguments, but {} were supplied.",
+                    args_expected.len(),
+                    args_supplied.len()
+                ),
+            )
+            .at(span));
         }
 
         let current_env = Rc::clone(&self.env);
@@ -493,12 +1049,553 @@ impl Evaluator {
         }
 
         self.env = Rc::new(RefCell::new(closure_env));
-        let obj = self.evaluate_block_statement(&body);
+        let result = self.evaluate_block_statement(&body);
         self.env = current_env;
 
-        match obj {
-            Some(obj) => obj,
-            None => NULL_OBJECT,
+        match result {
+            Ok(Object::RETURN(val)) => Ok(*val),
+            Ok(obj) => Ok(obj),
+            Err(mut error) => {
+                let name = match func {
+                    Expression::Identifier(ident) => ident.literal.clone(),
+                    _ => String::from("<anonymous>"),
+                };
+                error.stack.push(Frame {
+                    name,
+                    call_site: span,
+                });
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod evaluator_test {
+    use super::*;
+    use crate::{evaluator::builltin_funcs::new_builtins, lexer::Lexer, parser::Parser};
+
+    fn eval(input: &str) -> EvalResult {
+        let l = Lexer::new(input);
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        assert!(p.get_errors().is_empty(), "{:?}", p.get_errors());
+
+        let env = Environment::from(new_builtins());
+        Evaluator::new(Rc::new(RefCell::new(env))).evaluate(program)
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let tests = vec![
+            ("{\"a\": 1, \"b\": 2}[\"b\"]", Object::INTEGER(2)),
+            (
+                "{1: \"x\", true: \"y\"}[true]",
+                Object::STRING("y".to_string()),
+            ),
+            ("let k = \"a\"; {k: 5}[\"a\"]", Object::INTEGER(5)),
+            ("{\"a\": 1}[\"missing\"]", Object::NIL),
+            ("len({\"a\": 1, \"a\": 2})", Object::INTEGER(1)),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(expected), "{}", input);
+        }
+
+        let table = eval("{\"b\": 1, \"a\": 2, \"b\": 3}").unwrap();
+        assert_eq!(table.to_string(), "{\"b\": 3, \"a\": 2}");
+
+        match eval("{[1]: 2}") {
+            Err(error) => assert!(error.message.contains("Array cannot be used as a hash key")),
+            obj => panic!("Expected an error but received.. {:?}", obj),
+        }
+        match eval("{\"a\": 1}[func(x) { x }]") {
+            Err(_) => {}
+            obj => panic!("Expected an error but received.. {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn test_hash_builtins() {
+        let tests = vec![
+            ("keys({\"b\": 1, \"a\": 2})", "[\"b\", \"a\"]"),
+            ("values({\"b\": 1, \"a\": 2})", "[1, 2]"),
+            ("has({\"b\": 1}, \"b\")", "true"),
+            ("has({\"b\": 1}, \"c\")", "false"),
+            (
+                "delete({\"a\": 1, \"b\": 2, \"c\": 3}, \"b\")",
+                "{\"a\": 1, \"c\": 3}",
+            ),
+            ("let h = {\"a\": 1}; delete(h, \"a\"); h", "{\"a\": 1}"),
+            (
+                "merge({\"a\": 1, \"b\": 2}, {\"b\": 3, \"c\": 4})",
+                "{\"a\": 1, \"b\": 3, \"c\": 4}",
+            ),
+            ("delete({}, 1)", "{}"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input).unwrap().to_string(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let tests = vec![
+            ("3 <= 3", Object::BOOL(true)),
+            ("4 >= 5", Object::BOOL(false)),
+            ("7 % 3", Object::INTEGER(1)),
+            ("-7 % 3", Object::INTEGER(-1)),
+            ("2 ** 10", Object::INTEGER(1024)),
+            ("2 ** 3 ** 2", Object::INTEGER(512)),
+            ("-2 ** 2", Object::INTEGER(-4)),
+            ("6 & 3", Object::INTEGER(2)),
+            ("6 | 3", Object::INTEGER(7)),
+            ("6 ^ 3", Object::INTEGER(5)),
+            ("1 << 4", Object::INTEGER(16)),
+            ("-16 >> 2", Object::INTEGER(-4)),
+            ("true && false", Object::BOOL(false)),
+            ("false || true", Object::BOOL(true)),
+            ("1 < 2 && 2 < 3", Object::BOOL(true)),
+            ("false && undefined", Object::BOOL(false)),
+            ("true || undefined", Object::BOOL(true)),
+            (
+                "let f = func() { undefined };\nfalse && f()",
+                Object::BOOL(false),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(expected), "{}", input);
+        }
+
+        let errors = vec![
+            ("5 % 0", "Modulo by zero"),
+            ("2 ** -1", "Negative exponent"),
+            ("1 << -1", "Negative shift amount"),
+            ("true && undefined", "undefined has not been declared"),
+            ("true & false", "not supported"),
+        ];
+        for (input, expected) in errors {
+            match eval(input) {
+                Err(error) => assert!(error.message.contains(expected), "{}: {}", input, error),
+                obj => panic!("Expected an error for {} but received.. {:?}", input, obj),
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_integers() {
+        let tests = vec![
+            ("9223372036854775807 + 1", "9223372036854775808"),
+            ("-9223372036854775807 - 2", "-9223372036854775809"),
+            ("2 ** 64", "18446744073709551616"),
+            ("1 << 64", "18446744073709551616"),
+            ("(1 << 64) >> 60", "16"),
+            ("3037000500 * 3037000500", "9223372037000250000"),
+            ("2 ** 100 / 2 ** 98", "4"),
+            ("2 ** 100 % 7", "2"),
+            ("-(-9223372036854775807 - 1)", "9223372036854775808"),
+            ("(-9223372036854775807 - 1) / -1", "9223372036854775808"),
+            (
+                "let fact = func(n) { if (n < 2) { return 1; } n * fact(n - 1) }; fact(25)",
+                "15511210043330985984000000",
+            ),
+            ("2 ** 64 - 2 ** 64 + 1", "1"),
+            ("2 ** 64 > 2 ** 63", "true"),
+            ("2 ** 64 == int(\"18446744073709551616\")", "true"),
+            ("2 ** 64 * 0.5", "9.223372036854776e18"),
+            ("float(2 ** 70)", "1.1805916207174113e21"),
+            ("int(1e20)", "100000000000000000000"),
+            ("{2 ** 64: \"big\"}[1 << 64]", "\"big\""),
+            ("99999999999999999999 + 1", "100000000000000000000"),
+            ("0xFFFF_FFFF_FFFF_FFFF == 2 ** 64 - 1", "true"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input).unwrap().to_string(), expected, "{}", input);
+        }
+
+        assert_eq!(eval("2 ** 64 - 1 - 2 ** 64"), Ok(Object::INTEGER(-1)));
+        assert!(matches!(eval("2 ** 64"), Ok(Object::BIGINT(_))));
+        assert_eq!(eval("-9223372036854775808"), Ok(Object::INTEGER(i64::MIN)));
+
+        let errors = vec![
+            ("1 / 0", "Division by zero"),
+            ("2 ** 64 / 0", "Division by zero"),
+            ("2 ** 64 % 0", "Modulo by zero"),
+            ("10 ** 10 ** 10", "too large"),
+            (
+                "2 ** 64 & 1",
+                "only supported for integers that fit in 64 bits",
+            ),
+        ];
+        for (input, expected) in errors {
+            match eval(input) {
+                Err(error) => assert!(error.message.contains(expected), "{}: {}", input, error),
+                obj => panic!("Expected an error for {} but received.. {:?}", input, obj),
+            }
+        }
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let input = "let inner = func(x) { 1 / x };\nlet outer = func(x) { inner(x) };\nouter(0)";
+        let error = eval(input).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+        assert_eq!(
+            error
+                .stack
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["inner", "outer"]
+        );
+        assert_eq!(
+            error.traceback(),
+            "Traceback (most recent call last):\n  \
+             line 3:1, in <module>\n  \
+             line 2:23, in outer\n  \
+             line 1:23, in inner\n\
+             ZeroDivisionError: Division by zero"
+        );
+
+        let error = eval("func() { undefined }()").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::UndefinedName);
+        assert_eq!(error.stack[0].name, "<anonymous>");
+
+        let tests = vec![
+            ("len(1)", RuntimeErrorKind::TypeMismatch, "line 1:1"),
+            ("[1, 2][5]", RuntimeErrorKind::IndexOutOfBounds, "line 1:1"),
+            (
+                "let f = func(a) { a }; f()",
+                RuntimeErrorKind::ArityMismatch,
+                "line 1:24",
+            ),
+            (
+                "let x = 1; x(2)",
+                RuntimeErrorKind::NotCallable,
+                "line 1:12",
+            ),
+            ("int(\"abc\")", RuntimeErrorKind::InvalidValue, "line 1:1"),
+            ("[1, -true]", RuntimeErrorKind::TypeMismatch, "line 1:5"),
+        ];
+        for (input, kind, location) in tests {
+            let error = eval(input).unwrap_err();
+            assert_eq!(error.kind, kind, "{}", input);
+            assert_eq!(error.span.unwrap().to_string(), location, "{}", input);
+            assert!(error.stack.is_empty(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_loops() {
+        let tests = vec![
+            ("let i = 0; let sum = 0; while i < 5 { let sum = sum + i; let i = i + 1; }; sum", "10"),
+            ("let out = []; for x in [1, 2, 3] { let out = push(out, x * 2); }; out", "[2, 4, 6]"),
+            ("let out = \"\"; for ch in \"abc\" { let out = ch + out; }; out", "\"cba\""),
+            ("let out = []; for k in {\"b\": 1, \"a\": 2} { let out = push(out, k); }; out", "[\"b\", \"a\"]"),
+            (
+                "let out = []; for x in [1, 2, 3, 4, 5, 6] { if (x % 2 == 0) { continue; } if (x > 4) { break; } let out = push(out, x); }; out",
+                "[1, 3]",
+            ),
+            (
+                "let out = []; for x in [1, 2] { for y in [1, 2, 3] { if (y == 2) { break; } let out = push(out, [x, y]); } }; out",
+                "[[1, 1], [2, 1]]",
+            ),
+            (
+                "let find = func(items, target) { for x in items { if (x == target) { return true; } } false }; [find([1, 2], 2), find([1, 2], 3)]",
+                "[true, false]",
+            ),
+            ("let i = 0; while true { let i = i + 1; if (i == 100000) { break; } }; i", "100000"),
+            ("for x in [] { undefined }", "nil"),
+        ];
+        for (input, expected) in tests {
+            let result = eval(input).unwrap();
+            let result = match result {
+                Object::NIL => String::from("nil"),
+                obj => obj.to_string(),
+            };
+            assert_eq!(result, expected, "{}", input);
+        }
+
+        let error = eval("for x in 5 { x }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+        assert_eq!(error.message, "Int is not iterable");
+    }
+
+    #[test]
+    fn test_control_flow_in_expressions() {
+        let tests = vec![
+            ("let i = 0; while true { i += 1; let x = if (i > 3) { break } else { 1 }; }; i", "4"),
+            ("let n = 0; for x in [1, 2, 3] { n += if (x == 2) { continue } else { x }; }; n", "4"),
+            ("let out = []; for x in [1, 2, 3] { out = push(out, if (x == 2) { continue } else { x }); }; out", "[1, 3]"),
+            ("let out = []; for x in [1, 2, 3] { out = push(out, [if (x == 3) { break } else { x }]); }; out", "[[1], [2]]"),
+            ("let a = [0, 0]; for x in [1, 2] { a[if (x == 2) { break } else { x }] = x; }; a", "[0, 1]"),
+            ("let n = 0; for x in [1, 2, 3] { n = n + (if (x == 2) { continue } else { x }); }; n", "4"),
+            ("let s = \"\"; for x in [1, 2] { s += \"${if (x == 1) { continue } else { x }}\"; }; s", "\"2\""),
+            ("let f = func(x) { let y = if (x > 0) { return \"positive\" } else { x }; y }; [f(1), f(-1)]", "[\"positive\", -1]"),
+            ("let f = func(x) { len(if (x) { return 0 } else { [1, 2] }) }; [f(true), f(false)]", "[0, 2]"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input).unwrap().to_string(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_assignment() {
+        let tests = vec![
+            ("let x = 1; x = 2; x", "2"),
+            ("let x = 10; x += 5; x -= 3; x *= 2; x /= 4; x", "6"),
+            ("let s = \"a\"; s += \"b\"; s", "\"ab\""),
+            (
+                "let counter = func() { let n = 0; func() { n += 1; n } }; let next = counter(); next(); next(); next()",
+                "3",
+            ),
+            ("let x = 1; let f = func() { let x = 5; x = 6; x }; [f(), x]", "[6, 1]"),
+            ("let i = 0; while i < 3 { i += 1 }; i", "3"),
+            ("let a = [1, 2, 3]; a[1] = 20; a[2] += 10; a", "[1, 20, 13]"),
+            ("let h = {\"a\": 1}; h[\"b\"] = 2; h[\"a\"] *= 5; h", "{\"a\": 5, \"b\": 2}"),
+            ("let m = [[1, 2], [3, 4]]; m[1][0] = 9; m", "[[1, 2], [9, 4]]"),
+            ("let h = {\"xs\": [1]}; h[\"xs\"][0] = 2; h", "{\"xs\": [2]}"),
+            ("let a = [1, 2]; let b = a; b[0] = 9; [a, b]", "[[1, 2], [9, 2]]"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input).unwrap().to_string(), expected, "{}", input);
+        }
+        assert_eq!(eval("let x = 1; x = 2"), Ok(Object::NIL));
+
+        let errors = vec![
+            (
+                "y = 1",
+                RuntimeErrorKind::UndefinedName,
+                "Cannot assign to y",
+            ),
+            (
+                "y += 1",
+                RuntimeErrorKind::UndefinedName,
+                "Cannot assign to y",
+            ),
+            (
+                "let a = [1]; a[3] = 1",
+                RuntimeErrorKind::IndexOutOfBounds,
+                "out of bounds",
+            ),
+            (
+                "let a = 1; a[0] = 1",
+                RuntimeErrorKind::TypeMismatch,
+                "Cannot assign into",
+            ),
+            (
+                "let h = {}; h[[1]] = 1",
+                RuntimeErrorKind::TypeMismatch,
+                "hash key",
+            ),
+            (
+                "let x = 1; x /= 0",
+                RuntimeErrorKind::DivisionByZero,
+                "Division by zero",
+            ),
+        ];
+        for (input, kind, expected) in errors {
+            let error = eval(input).unwrap_err();
+            assert_eq!(error.kind, kind, "{}", input);
+            assert!(error.message.contains(expected), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn test_float_arithmetic() {
+        let tests = vec![
+            ("0.1 + 0.2", "0.30000000000000004"),
+            ("1 + 2.5", "3.5"),
+            ("2.5 * 2", "5.0"),
+            ("7 / 2.0", "3.5"),
+            ("-1.5", "-1.5"),
+            ("+1.5", "1.5"),
+            ("7.5 % 2", "1.5"),
+            ("2 ** 0.5", "1.4142135623730951"),
+            ("1e-9", "1e-9"),
+            ("1.5e20", "1.5e20"),
+            ("123456.0", "123456.0"),
+            ("1.0 / 0", "inf"),
+            ("1 == 1.0", "true"),
+            ("2 < 2.5", "true"),
+            ("2.5 >= 3", "false"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input).unwrap().to_string(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_number_builtins() {
+        let tests = vec![
+            ("int(3.99)", Object::INTEGER(3)),
+            ("int(-3.99)", Object::INTEGER(-3)),
+            ("int(\" 42 \")", Object::INTEGER(42)),
+            ("int(true)", Object::INTEGER(1)),
+            ("float(2)", Object::FLOAT(2.0)),
+            ("float(\"2.5\")", Object::FLOAT(2.5)),
+            ("round(2.5)", Object::INTEGER(3)),
+            ("round(-2.5)", Object::INTEGER(-3)),
+            ("round(12.34567, 2)", Object::FLOAT(12.35)),
+            ("round(0.1 + 0.2, 400)", Object::FLOAT(0.1 + 0.2)),
+            ("round(1e300, 14)", Object::FLOAT(1e300)),
+            ("round(1234.5, -2)", Object::FLOAT(1200.0)),
+            ("round(1234, -2)", Object::INTEGER(1200)),
+            ("round(-1250, -2)", Object::INTEGER(-1300)),
+            ("round(1234, 2)", Object::INTEGER(1234)),
+            ("round(49, -2)", Object::INTEGER(0)),
+            (
+                "round(9223372036854775807, -1000000000000)",
+                Object::INTEGER(0),
+            ),
+            (
+                "round(9223372036854775807, -19) == 10 ** 19",
+                Object::BOOL(true),
+            ),
+            ("round(2 ** 70, -20) == 12 * 10 ** 20", Object::BOOL(true)),
+            ("floor(-1.5)", Object::INTEGER(-2)),
+            ("ceil(1.2)", Object::INTEGER(2)),
+            ("floor(7)", Object::INTEGER(7)),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(expected), "{}", input);
+        }
+
+        for input in [
+            "int(\"abc\")",
+            "int(float(\"inf\"))",
+            "round(float(\"nan\"))",
+            "ceil(\"1\")",
+        ] {
+            assert!(eval(input).is_err(), "Expected an error for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let input = "
+/// Adds one to `x`.
+/// Works on floats too.
+let inc = func(x) { x + 1 }; // not part of the doc
+/* block comments /* nest */ */
+let plain = func(x) { x };
+/// The answer.
+let n = 42;
+let m = n;
+";
+        let tests = [
+            (
+                "doc(inc)",
+                Object::STRING("Adds one to `x`.\nWorks on floats too.".to_string()),
+            ),
+            ("doc(plain)", Object::NIL),
+            ("doc(n)", Object::STRING("The answer.".to_string())),
+            ("doc(m)", Object::NIL),
+            ("let n = 1; doc(n)", Object::NIL),
+            ("n = 1; doc(n)", Object::STRING("The answer.".to_string())),
+            ("let f = func(n) { doc(n) }; f(1)", Object::NIL),
+            (
+                "let f = func() { doc(n) }; f()",
+                Object::STRING("The answer.".to_string()),
+            ),
+            ("doc(len)", Object::NIL),
+            (
+                "let alias = inc; doc(alias)",
+                Object::STRING("Adds one to `x`.\nWorks on floats too.".to_string()),
+            ),
+        ];
+        for (expr, expected) in tests {
+            let program = format!("{}{}", input, expr);
+            assert_eq!(eval(&program), Ok(expected), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_unicode_strings() {
+        let tests = [
+            ("len(\"héllo\")", Object::INTEGER(5)),
+            ("len(\"日本語\")", Object::INTEGER(3)),
+            ("\"日本語\"[1]", Object::STRING("本".to_string())),
+            ("\"a\\tb\"[1]", Object::STRING("\t".to_string())),
+            ("len(\"\\u{1F600}\")", Object::INTEGER(1)),
+            (
+                "let café2 = \"ok\"; café2",
+                Object::STRING("ok".to_string()),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(expected), "{}", input);
+        }
+
+        for input in ["\"abc\"[3]", "\"abc\"[-1]", "\"abc\"[\"a\"]"] {
+            assert!(eval(input).is_err(), "Expected an error for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let tests = [
+            (
+                "let count = 3; \"total: ${count * 2} items\"",
+                "total: 6 items",
+            ),
+            ("\"${1.5} ${true} ${nil_value()}\"", "1.5 true nil"),
+            ("\"${[1, \"a\"]} ${\"b\"}\"", "[1, \"a\"] b"),
+            ("\"${2 ** 70}\"", "1180591620717411303424"),
+            ("let f = func(x, y) { x }; \"${f}\"", "func(x, y) { ... }"),
+            ("\"\\${escaped}\"", "${escaped}"),
+        ];
+        for (input, expected) in tests {
+            let input = format!("let nil_value = func() {{ if (false) {{ 1 }} }}; {}", input);
+            assert_eq!(
+                eval(&input),
+                Ok(Object::STRING(expected.to_string())),
+                "{}",
+                input
+            );
+        }
+        assert!(eval("\"${undefined}\"").is_err());
+    }
+
+    #[test]
+    fn test_type_names() {
+        let tests = vec![
+            ("1", "Int"),
+            ("2 ** 64", "Int"),
+            ("1.5", "Float"),
+            ("true", "Bool"),
+            ("if (false) { 1 }", "Nil"),
+            ("\"a\"", "String"),
+            ("[1]", "Array"),
+            ("{1: 2}", "Hash"),
+            ("func(x) { x }", "Function"),
+            ("len", "Builtin"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(Object::type_of(eval(input).unwrap()), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_repr_and_str() {
+        let tests = [
+            ("repr(\"a\\n\\\"b\\\"\")", "\"a\\n\\\"b\\\"\""),
+            ("str(\"a\\nb\")", "a\nb"),
+            ("repr(\"\\${x}\")", "\"\\${x}\""),
+            ("repr([1, \"a\", {\"k\": 2.0}])", "[1, \"a\", {\"k\": 2.0}]"),
+            ("str([1, \"a\"])", "[1, \"a\"]"),
+            ("repr(func(a, b) { a + b })", "func(a, b) { ... }"),
+            ("str(len)", "<builtin len>"),
+            ("repr(nil_value())", "nil"),
+            ("str(42)", "42"),
+        ];
+        for (input, expected) in tests {
+            let input = format!("let nil_value = func() {{ if (false) {{ 1 }} }}; {}", input);
+            assert_eq!(
+                eval(&input),
+                Ok(Object::STRING(expected.to_string())),
+                "{}",
+                input
+            );
         }
     }
 }
@@ -1,50 +1,60 @@
 use std::{cell::RefCell, fmt, rc::Rc};
 
-use crate::abstract_tree::{BlockOfStatements, Identifier};
+use crate::{
+    abstract_tree::{BlockOfStatements, Identifier},
+    lexer::quote,
+};
 
-use super::Environment;
+use super::{BigInt, Environment, HashTable, RuntimeError};
 
-pub type BuiltInFunc = fn(Vec<Object>) -> Object;
+pub type BuiltInFunc = fn(Vec<Object>) -> Result<Object, RuntimeError>;
 
+#[allow(
+    clippy::upper_case_acronyms,
+    non_snake_case,
+    unpredictable_function_pointer_comparisons
+)]
 #[derive(PartialEq, Debug, Clone)]
 pub enum Object {
     INTEGER(i64),
+    BIGINT(BigInt),
+    FLOAT(f64),
     BOOL(bool),
     NIL,
-    ERROR(String),
     RETURN(Box<Object>),
-    FUNCTION(Vec<Identifier>, BlockOfStatements, Rc<RefCell<Environment>>),
+    BREAK,
+    CONTINUE,
+    /// Parameters, body, captured environment and the `///` doc of the binding it was defined by.
+    FUNCTION(
+        Vec<Identifier>,
+        BlockOfStatements,
+        Rc<RefCell<Environment>>,
+        Option<String>,
+    ),
     STRING(String),
     BUILTIN {
+        name: &'static str,
         arity: i16,
         builtInFunc: BuiltInFunc,
     },
     ARRAY(Vec<Object>),
+    HASH(HashTable),
 }
 
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Object::INTEGER(n) => write!(f, "{}", n),
+            Object::BIGINT(n) => write!(f, "{}", n),
+            Object::FLOAT(n) => write!(f, "{}", format_float(*n)),
             Object::BOOL(b) => write!(f, "{}", b),
-            Object::NIL => write!(f, "\n"),
-            Object::ERROR(s) => write!(f, "Error: {}", s),
-            Object::RETURN(obj) => write!(f, "Return({})", obj),
-            Object::FUNCTION(params, body, _) => {
-                write!(f, "fn(")?;
-                for (i, param) in params.iter().enumerate() {
-                    if i > 0 {
-                        write!(f, ", ")?;
-                    }
-                    write!(f, "{:?}", param)?;
-                }
-                write!(f, ") {:?}", body)?;
-                Ok(())
-            }
-            Object::STRING(s) => write!(f, "\"{}\"", s),
-            Object::BUILTIN { arity, builtInFunc } => {
-                write!(f, "builtin ({}) {:?}", arity, builtInFunc)
-            }
+            Object::NIL => write!(f, "nil"),
+            Object::RETURN(obj) => write!(f, "{}", obj),
+            Object::BREAK => write!(f, "break"),
+            Object::CONTINUE => write!(f, "continue"),
+            Object::FUNCTION(params, ..) => write!(f, "{} {{ ... }}", signature(params)),
+            Object::STRING(s) => write!(f, "{}", quote(s)),
+            Object::BUILTIN { name, .. } => write!(f, "<builtin {}>", name),
             Object::ARRAY(arr) => {
                 write!(f, "[")?;
                 for (i, obj) in arr.iter().enumerate() {
@@ -55,25 +65,89 @@ impl fmt::Display for Object {
                 }
                 write!(f, "]")
             }
+            Object::HASH(table) => {
+                if table.is_empty() {
+                    return write!(f, "{{}}");
+                }
+                write!(f, "{{")?;
+                for (i, (key, value)) in table.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key.to_object(), value)?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
 impl Object {
+    /// Converts the value to the text it contributes to an interpolated string
+    /// or to `str`. Strings are used as they are; everything else is written
+    /// the way `Display` (and `repr`) writes it.
+    pub fn stringify(&self) -> String {
+        match self {
+            Object::STRING(s) => s.clone(),
+            Object::RETURN(obj) => obj.stringify(),
+            obj => obj.to_string(),
+        }
+    }
+
+    /// The name of the value's type as users see it. Integers are `Int`
+    /// whether or not they fit in 64 bits.
     pub fn type_of(object: Object) -> String {
         match object {
-            Object::INTEGER(_) => String::from("int64"),
-            Object::BOOL(_) => String::from("bool"),
-            Object::NIL => String::from("nil"),
-            Object::ERROR(_) => String::from("Error"),
+            Object::INTEGER(_) | Object::BIGINT(_) => String::from("Int"),
+            Object::FLOAT(_) => String::from("Float"),
+            Object::BOOL(_) => String::from("Bool"),
             Object::STRING(_) => String::from("String"),
-            Object::RETURN(ref value) => format!("{:#?}", value),
-            Object::FUNCTION(params, _, _) => format!("func ({:#?})", params),
-            Object::BUILTIN {
-                arity: _,
-                builtInFunc: _,
-            } => format!("builtin_func"),
-            Object::ARRAY(_) => format!("Array"),
+            Object::RETURN(value) => Object::type_of(*value),
+            // `break` and `continue` end a statement without producing a value.
+            Object::NIL | Object::BREAK | Object::CONTINUE => String::from("Nil"),
+            Object::FUNCTION(..) => String::from("Function"),
+            Object::BUILTIN { .. } => String::from("Builtin"),
+            Object::ARRAY(_) => String::from("Array"),
+            Object::HASH(_) => String::from("Hash"),
         }
     }
 }
+
+impl From<BigInt> for Object {
+    /// Wraps an arbitrary-precision result, demoting it to `INTEGER` when it fits.
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Object::INTEGER(value),
+            None => Object::BIGINT(value),
+        }
+    }
+}
+
+/// Formats a float so that it always reads back as a float: integral values keep
+/// a trailing `.0` and very large or very small magnitudes use exponent notation.
+pub fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        return String::from("nan");
+    }
+    if value.is_infinite() {
+        return String::from(if value > 0.0 { "inf" } else { "-inf" });
+    }
+
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(1e-4..1e16).contains(&magnitude) {
+        format!("{:e}", value)
+    } else if value.fract() == 0.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// Describes a function by its parameters, e.g. `func(x, y)`.
+fn signature(params: &[Identifier]) -> String {
+    let params = params
+        .iter()
+        .map(|param| param.literal.as_str())
+        .collect::<Vec<_>>();
+    format!("func({})", params.join(", "))
+}
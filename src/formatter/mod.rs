@@ -0,0 +1,239 @@
+//! `racoon fmt`: rewrites source in the canonical form `Display` gives the
+//! tree, keeping its comments.
+
+use std::{
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use crate::{
+    abstract_tree::SourceWriter,
+    diagnostic,
+    lexer::Lexer,
+    parser::Parser,
+    runner::{EXIT_FAILURE, EXIT_USAGE},
+    tkn::TokenKind,
+};
+
+pub const USAGE: &str = "usage: racoon fmt [--check] [files...]
+
+Formats each file in place, or standard input to standard output when no files
+are given. With --check, files are left alone and the exit status is 1 if any
+of them isn't formatted.";
+
+/// Runs `racoon fmt` with the arguments that follow `fmt`.
+pub fn run(args: &[String]) -> ExitCode {
+    let mut check = false;
+    let mut paths = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                eprintln!("racoon: unknown option `{}`\n{}", flag, USAGE);
+                return ExitCode::from(EXIT_USAGE);
+            }
+            path => paths.push(path),
+        }
+    }
+
+    if paths.is_empty() {
+        let mut source = String::new();
+        if let Err(error) = io::stdin().read_to_string(&mut source) {
+            eprintln!("racoon: can't read standard input: {}", error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+        return match format_source(&source) {
+            Ok(formatted) if check && formatted != source => {
+                eprintln!("racoon: standard input is not formatted");
+                ExitCode::from(EXIT_FAILURE)
+            }
+            Ok(_) if check => ExitCode::SUCCESS,
+            Ok(formatted) => {
+                print!("{}", formatted);
+                ExitCode::SUCCESS
+            }
+            Err(errors) => {
+                eprintln!("{}", errors);
+                ExitCode::from(EXIT_FAILURE)
+            }
+        };
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("racoon: can't read {}: {}", path, error);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        };
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                eprintln!("{}: {}", path, errors);
+                status = ExitCode::from(EXIT_FAILURE);
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("racoon: {} is not formatted", path);
+            status = ExitCode::from(EXIT_FAILURE);
+        } else if let Err(error) = std::fs::write(path, formatted) {
+            eprintln!("racoon: can't write {}: {}", path, error);
+            status = ExitCode::from(EXIT_FAILURE);
+        }
+    }
+    status
+}
+
+/// Formats a whole program, or returns its parse errors ready to print.
+pub fn format_source(source: &str) -> Result<String, String> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if !parser.get_errors().is_empty() {
+        let colour = diagnostic::use_colour();
+        let errors = parser
+            .get_errors()
+            .iter()
+            .map(|error| error.render(source, colour))
+            .collect::<Vec<_>>();
+        return Err(errors.join("\n\n"));
+    }
+
+    let mut formatted = match source.lines().next() {
+        Some(shebang) if shebang.starts_with("#!") => format!("{}\n", shebang.trim_end()),
+        _ => String::new(),
+    };
+    formatted.push_str(&SourceWriter::with_comments(source, comments(source)).program(&program));
+    Ok(formatted)
+}
+
+/// Spans of every comment in `source`, doc comments included.
+fn comments(source: &str) -> Vec<crate::tkn::Span> {
+    let mut lexer = Lexer::new(source);
+    let mut comments = vec![];
+    loop {
+        let tok = lexer.next_token();
+        match tok.kind {
+            TokenKind::EOF => break,
+            TokenKind::DOC => comments.push(tok.span),
+            _ => {}
+        }
+    }
+    comments.extend_from_slice(lexer.comments());
+    comments
+}
+
+#[cfg(test)]
+mod formatter_test {
+    use super::*;
+
+    #[test]
+    fn test_format_source() {
+        let tests = [
+            ("let   x=1+2*3", "let x = 1 + 2 * 3;\n"),
+            ("let f = func(a,b){a+b};", "let f = func(a, b) { a + b };\n"),
+            (
+                "if (x > 1) { let y = 2; y } else { 3 }",
+                "if x > 1 {\n    let y = 2;\n    y;\n} else { 3 }\n",
+            ),
+            (
+                "(1 + 2) * 3; 2 ** (3 ** 4); (2 ** 3) ** 4",
+                "(1 + 2) * 3;\n2 ** 3 ** 4;\n(2 ** 3) ** 4;\n",
+            ),
+            (
+                "1 - (2 - 3); (1 - 2) - 3; -(-x); (-x) ** 2; -(x ** 2)",
+                "1 - (2 - 3);\n1 - 2 - 3;\n-(-x);\n(-x) ** 2;\n-x ** 2;\n",
+            ),
+            (
+                "(func(x) { x })(1); (a + b)[0]; f(1)[2](3)",
+                "func(x) { x }(1);\n(a + b)[0];\nf(1)[2](3);\n",
+            ),
+            (
+                "\"a\\n${x + 1}\\${y}\"; {\"k\": [0x1F, 1_000, 2.50, 0x1_0000_0000_0000_0000]}",
+                "\"a\\n${x + 1}\\${y}\";\n{\"k\": [0x1F, 1_000, 2.50, 0x1_0000_0000_0000_0000]};\n",
+            ),
+            ("if (x) { 1 };\n[y]", "if x { 1 };\n[y];\n"),
+            ("if (x) { 1 }\n-y", "if x { 1 } - y;\n"),
+            ("if (x) { 1 }\nlet y = 2", "if x { 1 }\nlet y = 2;\n"),
+            (
+                "while i < 3 { i += 1; if (i == 2) { break } }",
+                "while i < 3 {\n    i += 1;\n    if i == 2 { break }\n}\n",
+            ),
+            ("for x in [] {}", "for x in [] {}\n"),
+            (
+                "let p = r\"C:\\dir\\n\"; \"\\u{e9}\\t\"",
+                "let p = r\"C:\\dir\\n\";\n\"\\u{e9}\\t\";\n",
+            ),
+            (
+                "let q = \"\"\"\n    SELECT *\n      FROM t\n    \"\"\"",
+                "let q = \"\"\"\n    SELECT *\n      FROM t\n    \"\"\";\n",
+            ),
+        ];
+        for (input, expected) in tests {
+            let formatted = format_source(input).unwrap();
+            assert_eq!(formatted, expected, "input: {}", input);
+            assert_eq!(format_source(&formatted).unwrap(), formatted);
+        }
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let input = "#!/usr/bin/env racoon
+// Leading comment.
+let x = 1;   // trailing
+
+
+/// Adds one.
+///
+let add = func(n) {
+    // inside
+    n + 1 /* after */
+};
+let y = [1, // one
+  2];
+for i in [] {
+    /* empty */
+}
+if x {
+    1
+} else {
+    // two
+    2
+}
+// the end
+";
+        let expected = "#!/usr/bin/env racoon
+// Leading comment.
+let x = 1; // trailing
+
+/// Adds one.
+///
+let add = func(n) {
+    // inside
+    n + 1; /* after */
+};
+let y = [1, 2]; // one
+for i in [] {
+    /* empty */
+}
+if x { 1 } else {
+    // two
+    2;
+}
+// the end
+";
+        let formatted = format_source(input).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+        assert!(format_source("let = 1").is_err());
+    }
+}
@@ -1,303 +1,799 @@
-use crate::tkn::{Token, TokenKind};
+mod xid;
+
+use crate::tkn::{Span, Token, TokenKind};
+
+/// Words that lex as their own token kind rather than as identifiers.
+pub const KEYWORDS: [(&str, TokenKind); 12] = [
+    ("func", TokenKind::FUNCTION),
+    ("let", TokenKind::LET),
+    ("true", TokenKind::TRUE(true)),
+    ("false", TokenKind::FALSE(false)),
+    ("if", TokenKind::IF),
+    ("else", TokenKind::ELSE),
+    ("return", TokenKind::RETURN),
+    ("while", TokenKind::WHILE),
+    ("for", TokenKind::FOR),
+    ("in", TokenKind::IN),
+    ("break", TokenKind::BREAK),
+    ("continue", TokenKind::CONTINUE),
+];
 
 pub struct Lexer<'a> {
     input: &'a str,
     pos: usize,
     peek_pos: usize,
-    ch: u8,
+    ch: char,
+    line: usize,
+    column: usize,
+    /// Open `${` interpolations, each with the number of `{` still unclosed inside it.
+    interpolations: Vec<usize>,
+    /// Spans of the `//` and `/* */` comments skipped so far.
+    comments: Vec<Span>,
 }
 
 impl<'a> Lexer<'a> {
+    #[allow(clippy::needless_return)]
     pub fn new(input: &'a str) -> Self {
         let mut lexer = Lexer {
             input,
             pos: 0,
             peek_pos: 0,
-            ch: 0,
+            ch: '\0',
+            line: 1,
+            column: 0,
+            interpolations: vec![],
+            comments: vec![],
         };
 
         lexer.read_char();
+        // A `#!` line lets scripts be run directly on Unix.
+        if input.starts_with("#!") {
+            while lexer.ch != '\n' && lexer.ch != '\0' {
+                lexer.read_char();
+            }
+        }
 
         return lexer;
     }
 
     fn read_char(&mut self) {
-        if self.peek_pos >= self.input.len() {
-            self.ch = 0;
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.ch = self.input.as_bytes()[self.peek_pos];
+            self.column += 1;
         }
         self.pos = self.peek_pos;
-        self.peek_pos += 1;
+        match self
+            .input
+            .get(self.peek_pos..)
+            .and_then(|rest| rest.chars().next())
+        {
+            Some(ch) => {
+                self.ch = ch;
+                self.peek_pos += ch.len_utf8();
+            }
+            None => {
+                self.ch = '\0';
+                self.peek_pos += 1;
+            }
+        }
     }
 
-    fn nextch(&mut self) -> u8 {
-        if self.peek_pos >= self.input.len() {
-            return 0;
-        } else {
-            return self.input.as_bytes()[self.peek_pos];
-        }
+    fn nextch(&self) -> char {
+        self.input
+            .get(self.peek_pos..)
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or('\0')
     }
 
-    fn nextch_is(&mut self, ch: u8) -> bool {
+    fn nextch_is(&self, ch: char) -> bool {
         self.nextch() == ch
     }
 
-    fn skip_whitespace(&mut self) {
+    /// Skips whitespace along with `//` and `/* */` comments. Doc comments are
+    /// left alone since they are tokens. An empty line is returned as a `BLANK`
+    /// token and a block comment that is never closed as an `ILLEGAL` one.
+    fn skip_whitespace(&mut self) -> Option<Token> {
         loop {
-            match self.ch {
-                b' ' | b'\t' => {
+            match (self.ch, self.nextch()) {
+                // An empty line is kept as a `BLANK` token.
+                ('\n', '\n') => {
+                    let mut tok = Token::new(TokenKind::BLANK, "\n".to_string());
+                    tok.span = Span::new(self.pos, self.pos + 1, self.line, self.column);
+                    self.read_char();
+                    return Some(tok);
+                }
+                (ch, _) if ch.is_whitespace() => self.read_char(),
+                ('/', '/') if !self.at_doc_comment() => {
+                    let (start, line, column) = (self.pos, self.line, self.column);
+                    while self.ch != '\n' && self.ch != '\0' {
+                        self.read_char();
+                    }
+                    let end = self.pos.min(self.input.len());
+                    self.comments.push(Span::new(start, end, line, column));
+                }
+                ('/', '*') => {
+                    let (start, line, column) = (self.pos, self.line, self.column);
+                    if !self.skip_block_comment() {
+                        let mut tok = Token::new(TokenKind::ILLEGAL, "/*".to_string());
+                        tok.span = Span::new(start, start + 2, line, column);
+                        return Some(tok);
+                    }
+                    let end = self.pos.min(self.input.len());
+                    self.comments.push(Span::new(start, end, line, column));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// `///` starts a doc comment, but `////` and longer runs are plain comments.
+    fn at_doc_comment(&self) -> bool {
+        let rest = &self.input.as_bytes()[self.pos.min(self.input.len())..];
+        rest.starts_with(b"///") && !rest.starts_with(b"////")
+    }
+
+    /// Consumes a `/* */` comment, which may contain nested block comments.
+    /// Returns `false` if the input ends before the comment is closed.
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match (self.ch, self.nextch()) {
+                ('\0', _) => return false,
+                ('/', '*') => {
+                    depth += 1;
                     self.read_char();
                 }
-                _ => {
-                    break;
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
+                    }
                 }
+                _ => {}
             }
+            self.read_char();
         }
     }
 
+    /// Spans of the `//` and `/* */` comments passed so far. Doc comments
+    /// aren't included since they are returned as `DOC` tokens.
+    pub fn comments(&self) -> &[Span] {
+        &self.comments
+    }
+
+    /// The source text that `span` covers.
+    pub fn text(&self, span: Span) -> &'a str {
+        &self.input[span.start..span.end]
+    }
+
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        if let Some(tok) = self.skip_whitespace() {
+            return tok;
+        }
 
+        let (start, line, column) = (self.pos.min(self.input.len()), self.line, self.column);
+        let mut tok = self.read_token();
+        tok.span = Span::new(start, self.pos.min(self.input.len()), line, column);
+
+        tok
+    }
+
+    fn read_token(&mut self) -> Token {
         let tok = match self.ch {
-            b'=' => {
-                if self.nextch_is(b'=') {
+            '=' => {
+                if self.nextch_is('=') {
                     self.read_char();
                     Token {
                         kind: TokenKind::EQ,
                         literal: "==".to_string(),
+                        span: Span::default(),
                     }
                 } else {
                     Token {
                         kind: TokenKind::ASSIGN,
                         literal: "=".to_string(),
+                        span: Span::default(),
                     }
                 }
             }
-            b'+' => Token {
-                kind: TokenKind::PLUS,
-                literal: "+".to_string(),
-            },
-            b'-' => Token {
-                kind: TokenKind::MINUS,
-                literal: "-".to_string(),
-            },
-            b'!' => {
-                if self.nextch_is(b'=') {
+            '+' => {
+                if self.nextch_is('=') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::PLUSEQ,
+                        literal: "+=".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::PLUS,
+                        literal: "+".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '-' => {
+                if self.nextch_is('=') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::MINUSEQ,
+                        literal: "-=".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::MINUS,
+                        literal: "-".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '!' => {
+                if self.nextch_is('=') {
                     self.read_char();
                     Token {
                         kind: TokenKind::NEQ,
                         literal: "!=".to_string(),
+                        span: Span::default(),
                     }
                 } else {
                     Token {
                         kind: TokenKind::BANG,
                         literal: "!".to_string(),
+                        span: Span::default(),
                     }
                 }
             }
-            b'/' => Token {
-                kind: TokenKind::SLASH,
-                literal: "/".to_string(),
+            '/' => {
+                if self.nextch_is('/') {
+                    return self.consume_doc_comment();
+                } else if self.nextch_is('=') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::SLASHEQ,
+                        literal: "/=".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::SLASH,
+                        literal: "/".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '*' => {
+                if self.nextch_is('*') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::POWER,
+                        literal: "**".to_string(),
+                        span: Span::default(),
+                    }
+                } else if self.nextch_is('=') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::ASTERISKEQ,
+                        literal: "*=".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::ASTERISK,
+                        literal: "*".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '%' => Token {
+                kind: TokenKind::PERCENT,
+                literal: "%".to_string(),
+                span: Span::default(),
             },
-            b'*' => Token {
-                kind: TokenKind::ASTERISK,
-                literal: "*".to_string(),
+            '&' => {
+                if self.nextch_is('&') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::AND,
+                        literal: "&&".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::AMPERSAND,
+                        literal: "&".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '|' => {
+                if self.nextch_is('|') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::OR,
+                        literal: "||".to_string(),
+                        span: Span::default(),
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::PIPE,
+                        literal: "|".to_string(),
+                        span: Span::default(),
+                    }
+                }
+            }
+            '^' => Token {
+                kind: TokenKind::CARET,
+                literal: "^".to_string(),
+                span: Span::default(),
             },
-            b'<' => {
-                if self.nextch_is(b'=') {
+            '<' => {
+                if self.nextch_is('=') {
                     self.read_char();
                     Token {
                         kind: TokenKind::LTE,
                         literal: "<=".to_string(),
+                        span: Span::default(),
+                    }
+                } else if self.nextch_is('<') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::SHL,
+                        literal: "<<".to_string(),
+                        span: Span::default(),
                     }
                 } else {
                     Token {
                         kind: TokenKind::LT,
                         literal: "<".to_string(),
+                        span: Span::default(),
                     }
                 }
             }
-            b'>' => {
-                if self.nextch_is(b'=') {
+            '>' => {
+                if self.nextch_is('=') {
                     self.read_char();
                     Token {
                         kind: TokenKind::GTE,
                         literal: ">=".to_string(),
+                        span: Span::default(),
+                    }
+                } else if self.nextch_is('>') {
+                    self.read_char();
+                    Token {
+                        kind: TokenKind::SHR,
+                        literal: ">>".to_string(),
+                        span: Span::default(),
                     }
                 } else {
                     Token {
                         kind: TokenKind::GT,
                         literal: ">".to_string(),
+                        span: Span::default(),
                     }
                 }
             }
-            b'(' => Token {
+            '(' => Token {
                 kind: TokenKind::LPAREN,
                 literal: "(".to_string(),
+                span: Span::default(),
             },
-            b')' => Token {
+            ')' => Token {
                 kind: TokenKind::RPAREN,
                 literal: ")".to_string(),
+                span: Span::default(),
             },
-            b'{' => Token {
-                kind: TokenKind::LBRACE,
-                literal: "{".to_string(),
-            },
-            b'}' => Token {
-                kind: TokenKind::RBRACE,
-                literal: "}".to_string(),
-            },
-            b'[' => Token {
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                Token {
+                    kind: TokenKind::LBRACE,
+                    literal: "{".to_string(),
+                    span: Span::default(),
+                }
+            }
+            '}' => {
+                match self.interpolations.last_mut() {
+                    Some(0) => {
+                        self.interpolations.pop();
+                        return self.consume_string(false);
+                    }
+                    Some(depth) => *depth -= 1,
+                    None => {}
+                }
+                Token {
+                    kind: TokenKind::RBRACE,
+                    literal: "}".to_string(),
+                    span: Span::default(),
+                }
+            }
+            '[' => Token {
                 kind: TokenKind::LBRACKET,
                 literal: "[".to_string(),
+                span: Span::default(),
             },
-            b']' => Token {
+            ']' => Token {
                 kind: TokenKind::RBRACKET,
                 literal: "]".to_string(),
+                span: Span::default(),
             },
-            b',' => Token {
+            ',' => Token {
                 kind: TokenKind::COMMA,
                 literal: ",".to_string(),
+                span: Span::default(),
             },
-            b';' => Token {
+            ';' => Token {
                 kind: TokenKind::SEMICOLON,
                 literal: ";".to_string(),
+                span: Span::default(),
             },
-            // b':' => Token{kind: TokenKind::COLON,literal:"".to_string()},
-            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
-                return self.consume_identifier();
-            }
-            b'0'..=b'9' => {
-                return self.consume_number();
-            }
-            b'"' => {
-                return self.consume_string();
-            }
-            b'\n' => {
-                if self.nextch_is(b'\n') {
-                    Token {
-                        kind: TokenKind::BLANK,
-                        literal: "\n".to_string(),
-                    }
-                } else {
-                    self.read_char();
-                    return self.next_token();
-                }
-            }
-            0 => Token {
-                kind: TokenKind::EOF,
-                literal: "EOF".to_string(),
+            ':' => Token {
+                kind: TokenKind::COLON,
+                literal: ":".to_string(),
+                span: Span::default(),
             },
-            _ => Token {
+            '0'..='9' => return self.consume_number(),
+            'r' if self.at_raw_string() => return self.consume_raw_string(),
+            ch if is_identifier_start(ch) => return self.consume_identifier(),
+            '"' if self.input[self.pos..].starts_with(r#"""""#) => {
+                return self.consume_multiline_string()
+            }
+            '"' => return self.consume_string(true),
+            '\0' => Token::default(),
+            ch => Token {
                 kind: TokenKind::ILLEGAL,
-                literal: "".to_string(),
+                literal: ch.to_string(),
+                span: Span::default(),
             },
         };
 
         self.read_char();
 
-        return tok;
+        tok
+    }
+
+    /// Reads a `///` comment up to the end of its line. The literal is the text
+    /// of the comment without the slashes and the space that usually follows them.
+    fn consume_doc_comment(&mut self) -> Token {
+        for _ in 0..3 {
+            self.read_char();
+        }
+        if self.ch == ' ' {
+            self.read_char();
+        }
+
+        let start_pos = self.pos.min(self.input.len());
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
+        }
+        let literal = self.input[start_pos..self.pos.min(self.input.len())].trim_end();
+
+        Token::new(TokenKind::DOC, literal.to_string())
     }
 
     fn consume_identifier(&mut self) -> Token {
         let start_pos = self.pos;
 
-        loop {
-            match self.ch {
-                b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
-                    self.read_char();
-                }
-                _ => {
-                    break;
-                }
-            }
+        while is_identifier_continue(self.ch) {
+            self.read_char();
         }
 
         let literal = &self.input[start_pos..self.pos];
 
-        match literal {
-            "func" => Token {
-                kind: TokenKind::FUNCTION,
-                literal: "func".to_string(),
-            },
-            "let" => Token {
-                kind: TokenKind::LET,
-                literal: "let".to_string(),
-            },
-            "true" => Token {
-                kind: TokenKind::TRUE(true),
-                literal: "true".to_string(),
-            },
-            "false" => Token {
-                kind: TokenKind::FALSE(false),
-                literal: "false".to_string(),
-            },
-            "if" => Token {
-                kind: TokenKind::IF,
-                literal: "if".to_string(),
-            },
-            "else" => Token {
-                kind: TokenKind::ELSE,
-                literal: "else".to_string(),
-            },
-            "return" => Token {
-                kind: TokenKind::RETURN,
-                literal: "return".to_string(),
-            },
-
-            _ => Token {
-                kind: TokenKind::IDENT,
-                literal: literal.to_string(),
-            },
+        match KEYWORDS.iter().find(|(keyword, _)| *keyword == literal) {
+            Some((keyword, kind)) => Token::new(*kind, keyword.to_string()),
+            None => Token::new(TokenKind::IDENT, literal.to_string()),
         }
     }
 
+    /// Reads a number. Digits may be separated with `_`, and integers may be
+    /// written in hex, octal or binary with a `0x`, `0o` or `0b` prefix. The
+    /// literal keeps the source text; the parser checks and converts it.
     fn consume_number(&mut self) -> Token {
         let start_pos = self.pos;
 
-        loop {
-            match self.ch {
-                b'0'..=b'9' => {
+        if self.ch == '0' && matches!(self.nextch(), 'x' | 'X' | 'o' | 'O' | 'b' | 'B') {
+            self.read_char();
+            self.read_char();
+            // Invalid digits are taken too, so `0b102` is reported as a bad literal.
+            while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+                self.read_char();
+            }
+            return Token::new(TokenKind::INT, self.input[start_pos..self.pos].to_string());
+        }
+
+        let mut kind = TokenKind::INT;
+
+        self.skip_digits();
+
+        if self.ch == '.' && self.nextch().is_ascii_digit() {
+            kind = TokenKind::FLOAT;
+            self.read_char();
+            self.skip_digits();
+        }
+
+        // An exponent is only part of the number when digits follow it, so `2e`
+        // still lexes as `2` followed by the identifier `e`.
+        if let 'e' | 'E' = self.ch {
+            let bytes = self.input.as_bytes();
+            let mut end = self.peek_pos;
+            if let Some(b'+' | b'-') = bytes.get(end) {
+                end += 1;
+            }
+            if bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                kind = TokenKind::FLOAT;
+                while self.peek_pos < end {
                     self.read_char();
                 }
-                _ => {
-                    break;
-                }
+                self.read_char();
+                self.skip_digits();
             }
         }
 
         let literal = &self.input[start_pos..self.pos];
 
-        Token {
-            kind: TokenKind::INT,
-            literal: literal.to_string(),
+        Token::new(kind, literal.to_string())
+    }
+
+    fn skip_digits(&mut self) {
+        while self.ch.is_ascii_digit() || self.ch == '_' {
+            self.read_char();
         }
     }
 
-    fn consume_string(&mut self) -> Token {
+    /// Reads a string literal, resolving its escape sequences. A malformed escape
+    /// makes the whole literal an `ILLEGAL` token holding the offending sequence.
+    ///
+    /// A string containing `${` is split into parts: the text up to the first
+    /// interpolation is a `STRINGHEAD`, and the lexer goes back to producing
+    /// ordinary tokens until the matching `}`, where it resumes the string with
+    /// a `STRINGMID` or `STRINGTAIL`. `at_start` is false when resuming.
+    fn consume_string(&mut self, at_start: bool) -> Token {
         self.read_char();
+        let mut value = String::new();
+        let mut invalid_escape = None;
+
+        let kind = loop {
+            match self.ch {
+                '\0' => return Token::new(TokenKind::ILLEGAL, "\"".to_string()),
+                '"' => {
+                    self.read_char();
+                    break if at_start {
+                        TokenKind::STRING
+                    } else {
+                        TokenKind::STRINGTAIL
+                    };
+                }
+                '$' if self.nextch_is('{') => {
+                    self.read_char();
+                    self.read_char();
+                    self.interpolations.push(0);
+                    break if at_start {
+                        TokenKind::STRINGHEAD
+                    } else {
+                        TokenKind::STRINGMID
+                    };
+                }
+                '\\' => {
+                    let start_pos = self.pos;
+                    let (ch, len) = unescape(self.input.get(self.peek_pos..).unwrap_or_default());
+                    let end_pos = self.peek_pos + len;
+                    while self.pos < end_pos {
+                        self.read_char();
+                    }
+                    match ch {
+                        Some(ch) => value.push(ch),
+                        None => {
+                            invalid_escape.get_or_insert(&self.input[start_pos..end_pos]);
+                        }
+                    }
+                }
+                ch => {
+                    value.push(ch);
+                    self.read_char();
+                }
+            }
+        };
+
+        match invalid_escape {
+            Some(escape) => Token::new(TokenKind::ILLEGAL, escape.to_string()),
+            None => Token::new(kind, value),
+        }
+    }
 
+    /// Reads a `"""` string. The text may span several lines and is dedented
+    /// with [`strip_indentation`] before its escapes are resolved.
+    fn consume_multiline_string(&mut self) -> Token {
+        for _ in 0..3 {
+            self.read_char();
+        }
         let start_pos = self.pos;
 
-        loop {
+        while !self.input[self.pos.min(self.input.len())..].starts_with(r#"""""#) {
             match self.ch {
-                b'"' | 0 => {
-                    let literal = &self.input[start_pos..self.pos];
+                '\0' => return Token::new(TokenKind::ILLEGAL, r#"""""#.to_string()),
+                // An escaped quote can't close the string.
+                '\\' => {
+                    self.read_char();
                     self.read_char();
-                    return Token {
-                        kind: TokenKind::STRING,
-                        literal: literal.to_string(),
-                    };
                 }
-                _ => {
+                _ => self.read_char(),
+            }
+        }
+        let raw = &self.input[start_pos..self.pos];
+        for _ in 0..3 {
+            self.read_char();
+        }
+
+        let text = strip_indentation(raw);
+        let mut value = String::new();
+        let mut rest = text.as_str();
+        while let Some(i) = rest.find('\\') {
+            value.push_str(&rest[..i]);
+            let (ch, len) = unescape(&rest[i + 1..]);
+            match ch {
+                Some(ch) => value.push(ch),
+                None => return Token::new(TokenKind::ILLEGAL, rest[i..i + 1 + len].to_string()),
+            }
+            rest = &rest[i + 1 + len..];
+        }
+        value.push_str(rest);
+
+        Token::new(TokenKind::STRING, value)
+    }
+
+    /// Reads `r"..."` or `r#"..."#`, whose text is taken as it is written. Each
+    /// `#` in the opening delimiter must be repeated after the closing quote,
+    /// which lets the string contain `"`.
+    fn consume_raw_string(&mut self) -> Token {
+        self.read_char();
+        let mut hashes = 0;
+        while self.ch == '#' {
+            hashes += 1;
+            self.read_char();
+        }
+        self.read_char();
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let start_pos = self.pos.min(self.input.len());
+        match self.input[start_pos..].find(&closing) {
+            Some(len) => {
+                while self.pos < start_pos + len + closing.len() {
+                    self.read_char();
+                }
+                let literal = &self.input[start_pos..start_pos + len];
+                Token::new(TokenKind::STRING, literal.to_string())
+            }
+            None => {
+                while self.ch != '\0' {
                     self.read_char();
                 }
+                Token::new(TokenKind::ILLEGAL, format!("r{}\"", "#".repeat(hashes)))
             }
         }
     }
+
+    /// Whether the lexer is on the `r` that opens a raw string.
+    fn at_raw_string(&self) -> bool {
+        let rest = &self.input[self.pos.min(self.input.len())..];
+        rest.starts_with('r') && rest[1..].trim_start_matches('#').starts_with('"')
+    }
+}
+
+/// Writes `s` as a string literal that reads back as the same string.
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+/// Escapes `s` for use between the quotes of a string literal, undoing what
+/// `unescape` does.
+pub fn escape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Decodes the escape sequence that follows a backslash at the start of `rest`.
+/// Returns the character it stands for, or `None` if it is malformed, along
+/// with the number of bytes the sequence spans.
+fn unescape(rest: &str) -> (Option<char>, usize) {
+    let ch = match rest.chars().next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('"') => '"',
+        Some('\\') => '\\',
+        Some('$') => '$',
+        Some('u') => return unescape_unicode(&rest[1..]),
+        Some(ch) => return (None, ch.len_utf8()),
+        None => return (None, 0),
+    };
+    (Some(ch), 1)
+}
+
+/// Decodes the `{...}` part of a `\u{...}` escape, which holds one to six hex
+/// digits of a Unicode scalar value.
+fn unescape_unicode(rest: &str) -> (Option<char>, usize) {
+    let Some(body) = rest.strip_prefix('{') else {
+        return (None, 1);
+    };
+    let digits_len = body
+        .find(|ch: char| !ch.is_ascii_hexdigit())
+        .unwrap_or(body.len());
+    let digits = &body[..digits_len];
+    if !body[digits_len..].starts_with('}') {
+        return (None, 2 + digits_len);
+    }
+
+    let ch = match digits.len() {
+        1..=6 => u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32),
+        _ => None,
+    };
+    (ch, 3 + digits_len)
 }
+
+/// Removes the layout of a `"""` string from its text: a line break right
+/// after the opening quotes, the line holding the closing quotes when nothing
+/// else is on it, and the indentation shared by every non-blank line.
+fn strip_indentation(raw: &str) -> String {
+    let raw = raw
+        .strip_prefix("\r\n")
+        .or_else(|| raw.strip_prefix('\n'))
+        .unwrap_or(raw);
+    let mut lines = raw.split('\n').collect::<Vec<_>>();
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let indentation = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| &line[common.min(indentation(line))..])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Identifiers follow Unicode's identifier syntax (UAX #31): they start with an
+/// `XID_Start` character or an underscore and continue with `XID_Continue` ones.
+fn is_identifier_start(ch: char) -> bool {
+    ch == '_' || xid::is_xid_start(ch)
+}
+
+fn is_identifier_continue(ch: char) -> bool {
+    xid::is_xid_continue(ch)
+}
+
 #[cfg(test)]
+// The original tests predate the lints the tree is now checked with.
+#[allow(clippy::useless_vec, clippy::to_string_in_format_args)]
 mod lexer_test {
     use super::*;
 
@@ -427,7 +923,7 @@ mod lexer_test {
     #[test]
     fn test_next_token4() {
         let input = "
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             10 == 10;
@@ -446,6 +942,7 @@ mod lexer_test {
             (TokenKind::GT, ">"),
             (TokenKind::INT, "5"),
             (TokenKind::SEMICOLON, ";"),
+            (TokenKind::BLANK, "\n"),
             (TokenKind::INT, "10"),
             (TokenKind::EQ, "=="),
             (TokenKind::INT, "10"),
@@ -505,4 +1002,436 @@ mod lexer_test {
             );
         }
     }
+
+    #[test]
+    fn test_spans() {
+        let input = "let x = 5;
+  x == 10";
+        let tests = vec![
+            (TokenKind::LET, Span::new(0, 3, 1, 1)),
+            (TokenKind::IDENT, Span::new(4, 5, 1, 5)),
+            (TokenKind::ASSIGN, Span::new(6, 7, 1, 7)),
+            (TokenKind::INT, Span::new(8, 9, 1, 9)),
+            (TokenKind::SEMICOLON, Span::new(9, 10, 1, 10)),
+            (TokenKind::IDENT, Span::new(13, 14, 2, 3)),
+            (TokenKind::EQ, Span::new(15, 17, 2, 5)),
+            (TokenKind::INT, Span::new(18, 20, 2, 8)),
+            (TokenKind::EOF, Span::new(20, 20, 2, 10)),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.span, tt.1,
+                "tests[{}] - span wrong. expected={:?}, got={:?}",
+                i, tt.1, tok.span
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        let input = "{\"one\": 1, true: 2}";
+        let tests = vec![
+            (TokenKind::LBRACE, "{"),
+            (TokenKind::STRING, "one"),
+            (TokenKind::COLON, ":"),
+            (TokenKind::INT, "1"),
+            (TokenKind::COMMA, ","),
+            (TokenKind::TRUE(true), "true"),
+            (TokenKind::COLON, ":"),
+            (TokenKind::INT, "2"),
+            (TokenKind::RBRACE, "}"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let input = "a <= b >= c && d || e % f ** g & h | i ^ j << k >> l * m";
+        let tests = vec![
+            (TokenKind::IDENT, "a"),
+            (TokenKind::LTE, "<="),
+            (TokenKind::IDENT, "b"),
+            (TokenKind::GTE, ">="),
+            (TokenKind::IDENT, "c"),
+            (TokenKind::AND, "&&"),
+            (TokenKind::IDENT, "d"),
+            (TokenKind::OR, "||"),
+            (TokenKind::IDENT, "e"),
+            (TokenKind::PERCENT, "%"),
+            (TokenKind::IDENT, "f"),
+            (TokenKind::POWER, "**"),
+            (TokenKind::IDENT, "g"),
+            (TokenKind::AMPERSAND, "&"),
+            (TokenKind::IDENT, "h"),
+            (TokenKind::PIPE, "|"),
+            (TokenKind::IDENT, "i"),
+            (TokenKind::CARET, "^"),
+            (TokenKind::IDENT, "j"),
+            (TokenKind::SHL, "<<"),
+            (TokenKind::IDENT, "k"),
+            (TokenKind::SHR, ">>"),
+            (TokenKind::IDENT, "l"),
+            (TokenKind::ASTERISK, "*"),
+            (TokenKind::IDENT, "m"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_float() {
+        let input = "3.14 1e-9 2.5E+3 7e2 10. 2e x.5";
+        let tests = vec![
+            (TokenKind::FLOAT, "3.14"),
+            (TokenKind::FLOAT, "1e-9"),
+            (TokenKind::FLOAT, "2.5E+3"),
+            (TokenKind::FLOAT, "7e2"),
+            (TokenKind::INT, "10"),
+            (TokenKind::ILLEGAL, "."),
+            (TokenKind::INT, "2"),
+            (TokenKind::IDENT, "e"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::ILLEGAL, "."),
+            (TokenKind::INT, "5"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_loop_keywords() {
+        let input = "while for in break continue format";
+        let tests = [
+            (TokenKind::WHILE, "while"),
+            (TokenKind::FOR, "for"),
+            (TokenKind::IN, "in"),
+            (TokenKind::BREAK, "break"),
+            (TokenKind::CONTINUE, "continue"),
+            (TokenKind::IDENT, "format"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_assignment_operators() {
+        let input = "x = 1; x += 2; x -= -3; x *= 4; x /= 5; x ** 2 == x";
+        let tests = [
+            (TokenKind::IDENT, "x"),
+            (TokenKind::ASSIGN, "="),
+            (TokenKind::INT, "1"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::PLUSEQ, "+="),
+            (TokenKind::INT, "2"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::MINUSEQ, "-="),
+            (TokenKind::MINUS, "-"),
+            (TokenKind::INT, "3"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::ASTERISKEQ, "*="),
+            (TokenKind::INT, "4"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::SLASHEQ, "/="),
+            (TokenKind::INT, "5"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::POWER, "**"),
+            (TokenKind::INT, "2"),
+            (TokenKind::EQ, "=="),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// a line comment
+let x = 1; // trailing
+/* a block /* with a nested */ comment */ x /*inline*/ / 2;
+//// not a doc comment
+///   Adds one.
+/// Second line.
+let f = 1;
+/* never closed";
+        let tests = [
+            (TokenKind::LET, "let"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::ASSIGN, "="),
+            (TokenKind::INT, "1"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::SLASH, "/"),
+            (TokenKind::INT, "2"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::DOC, "  Adds one."),
+            (TokenKind::DOC, "Second line."),
+            (TokenKind::LET, "let"),
+            (TokenKind::IDENT, "f"),
+            (TokenKind::ASSIGN, "="),
+            (TokenKind::INT, "1"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::ILLEGAL, "/*"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2:?}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+
+        let mut l = Lexer::new("1 /* two\nlines */ 2");
+        l.next_token();
+        assert_eq!(l.next_token().span, Span::new(18, 19, 2, 10));
+    }
+
+    #[test]
+    fn test_unicode_and_escapes() {
+        let input = r#"let x1 = "tab\there"; naïve_2 + 日本;
+"quote \" and \\ and \u{e9}\n" "bad \q" "bad \u{110000}" "unicode é""#;
+        let tests = [
+            (TokenKind::LET, "let"),
+            (TokenKind::IDENT, "x1"),
+            (TokenKind::ASSIGN, "="),
+            (TokenKind::STRING, "tab\there"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "naïve_2"),
+            (TokenKind::PLUS, "+"),
+            (TokenKind::IDENT, "日本"),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::STRING, "quote \" and \\ and é\n"),
+            (TokenKind::ILLEGAL, "\\q"),
+            (TokenKind::ILLEGAL, "\\u{110000}"),
+            (TokenKind::STRING, "unicode é"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+
+        let mut l = Lexer::new("é + b");
+        let tokens = [l.next_token(), l.next_token(), l.next_token()];
+        assert_eq!(tokens[0].span, Span::new(0, 2, 1, 1));
+        assert_eq!(tokens[1].span, Span::new(3, 4, 1, 3));
+        assert_eq!(tokens[2].span, Span::new(5, 6, 1, 5));
+    }
+
+    #[test]
+    fn test_identifier_characters() {
+        // `²` is a number but not a digit, and combining marks can't start a name.
+        let input = "x² e\u{301}tat \u{301}x Δx٣ ℕ _1 a\u{200d}";
+        let tests = [
+            (TokenKind::IDENT, "x"),
+            (TokenKind::ILLEGAL, "²"),
+            (TokenKind::IDENT, "e\u{301}tat"),
+            (TokenKind::ILLEGAL, "\u{301}"),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::IDENT, "Δx٣"),
+            (TokenKind::IDENT, "ℕ"),
+            (TokenKind::IDENT, "_1"),
+            (TokenKind::IDENT, "a"),
+            (TokenKind::ILLEGAL, "\u{200d}"),
+        ];
+        let mut l = Lexer::new(input);
+        for (kind, literal) in tests {
+            let tok = l.next_token();
+            assert_eq!((tok.kind, tok.literal.as_str()), (kind, literal));
+        }
+
+        assert!(xid::is_xid_start('日') && !xid::is_xid_start('1'));
+        assert!(xid::is_xid_continue('\u{301}') && !xid::is_xid_continue('²'));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let input = r#""total: ${count * 2} items" "${ {"a": "}"}["a"] }${x}" "\${x}""#;
+        let tests = [
+            (TokenKind::STRINGHEAD, "total: "),
+            (TokenKind::IDENT, "count"),
+            (TokenKind::ASTERISK, "*"),
+            (TokenKind::INT, "2"),
+            (TokenKind::STRINGTAIL, " items"),
+            (TokenKind::STRINGHEAD, ""),
+            (TokenKind::LBRACE, "{"),
+            (TokenKind::STRING, "a"),
+            (TokenKind::COLON, ":"),
+            (TokenKind::STRING, "}"),
+            (TokenKind::RBRACE, "}"),
+            (TokenKind::LBRACKET, "["),
+            (TokenKind::STRING, "a"),
+            (TokenKind::RBRACKET, "]"),
+            (TokenKind::STRINGMID, ""),
+            (TokenKind::IDENT, "x"),
+            (TokenKind::STRINGTAIL, ""),
+            (TokenKind::STRING, "${x}"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_raw_and_multiline_strings() {
+        let input = r###"r"C:\path\n" r#"say "hi" \${x}"# r##"a "# b"## rate
+let q = """
+    SELECT *
+      FROM t\t\"""
+    WHERE "id" = 1;
+    """;
+"""inline "quotes" \""" too"""
+"""
+    keep
+
+    blank
+  lines"""
+"###;
+        let tests = [
+            (TokenKind::STRING, r"C:\path\n"),
+            (TokenKind::STRING, r#"say "hi" \${x}"#),
+            (TokenKind::STRING, r##"a "# b"##),
+            (TokenKind::IDENT, "rate"),
+            (TokenKind::LET, "let"),
+            (TokenKind::IDENT, "q"),
+            (TokenKind::ASSIGN, "="),
+            (
+                TokenKind::STRING,
+                "SELECT *\n  FROM t\t\"\"\"\nWHERE \"id\" = 1;",
+            ),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::STRING, "inline \"quotes\" \"\"\" too"),
+            (TokenKind::STRING, "  keep\n\n  blank\nlines"),
+            (TokenKind::EOF, " "),
+        ];
+        let mut l = Lexer::new(input);
+        for (i, tt) in tests.iter().enumerate() {
+            let tok = l.next_token();
+            assert_eq!(
+                tok.kind, tt.0,
+                "tests[{0}] - tokentype wrong. expected={1:?}, got={2}",
+                i, tt.0, tok.kind
+            );
+            assert_eq!(
+                tok.literal, tt.1,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                i, tt.1, tok.literal
+            );
+        }
+
+        for (input, literal) in [("r#\"open\"", "r#\""), ("\"\"\"open\"", "\"\"\"")] {
+            let tok = Lexer::new(input).next_token();
+            assert_eq!(
+                (tok.kind, tok.literal.as_str()),
+                (TokenKind::ILLEGAL, literal)
+            );
+        }
+    }
 }
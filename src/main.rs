@@ -1,12 +1,57 @@
 mod abstract_tree;
+mod diagnostic;
+mod dump;
 mod evaluator;
+mod formatter;
 pub mod lexer;
+mod optimiser;
 pub mod parser;
 mod repl;
+mod runner;
 pub mod tkn;
 
+use std::process::ExitCode;
+
 use repl::REPL;
 
-fn main() {
-    REPL::default().run();
+const USAGE: &str = "usage: racoon [-O0] [script.rcn [args...]]
+       racoon [-O0] -e 'code' [args...]
+       racoon fmt [--check] [files...]
+       racoon --dump-tokens[=json|sexp] file
+       racoon --dump-ast[=json|sexp] file
+       racoon --load-ast file.json
+
+With no arguments, racoon starts an interactive session. `-O0` runs programs
+exactly as parsed, without optimising them first.";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let optimise = args.first().is_none_or(|flag| flag != "-O0");
+    if !optimise {
+        args.remove(0);
+    }
+    match args.as_slice() {
+        [] => {
+            REPL::new(optimise).run();
+            ExitCode::SUCCESS
+        }
+        [flag, ..] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        [flag, code, rest @ ..] if flag == "-e" => runner::run_source(code, rest, optimise),
+        [command, rest @ ..] if command == "fmt" => formatter::run(rest),
+        [flag, rest @ ..] if flag.starts_with("--dump-") || flag == "--load-ast" => {
+            dump::run(flag, rest)
+        }
+        [flag, ..] if flag.starts_with('-') && flag.len() > 1 => {
+            match flag.as_str() {
+                "-e" => eprintln!("racoon: `-e` needs the code to run"),
+                _ => eprintln!("racoon: unknown option `{}`", flag),
+            }
+            eprintln!("{}", USAGE);
+            ExitCode::from(runner::EXIT_USAGE)
+        }
+        [path, rest @ ..] => runner::run_file(path, rest, optimise),
+    }
 }
@@ -0,0 +1,624 @@
+//! Rewrites a parsed program into one that computes the same thing with less
+//! work, before it is evaluated:
+//!
+//! - operators applied to literals are computed once, using the evaluator's own
+//!   arithmetic, unless they raise an error or produce a big integer;
+//! - an `if` whose condition is a literal is replaced by the branch it takes;
+//! - statements after a `return`, `break` or `continue` are dropped;
+//! - calls to tiny top-level functions are replaced by their bodies.
+//!
+//! An error raised inside an inlined body is reported without the frame of the
+//! call it replaced; `-O0` turns the optimiser off to get full tracebacks.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    abstract_tree::{
+        fold::{self, Folder},
+        visit::{self, Visitor},
+        BlockOfStatements, Expression, Identifier, Infix, Literal, Program, Statement,
+    },
+    evaluator::{builltin_funcs::is_builtin, Environment, Evaluator, Object},
+    tkn::{Span, Token, TokenKind},
+};
+
+/// Longest string a constant is folded into, so that `"ab" * 100000` doesn't
+/// end up copied into the tree.
+const MAX_FOLDED_STRING: usize = 256;
+/// Most nodes a function body may have to be inlined.
+const MAX_INLINED_NODES: usize = 12;
+
+/// Optimises a whole script.
+pub fn optimise(program: Program) -> Program {
+    Optimiser::new(&program, true).program(program)
+}
+
+/// Optimises one REPL input. Functions aren't inlined, as a later input could
+/// rebind them or shadow the builtins they use.
+pub fn optimise_input(program: Program) -> Program {
+    Optimiser::new(&program, false).program(program)
+}
+
+/// A function that calls can be replaced with.
+struct Inlinable {
+    params: Vec<Identifier>,
+    body: Expression,
+}
+
+struct Optimiser {
+    /// Computes folded constants. Its environment stays empty.
+    evaluator: Evaluator,
+    inline: bool,
+    /// How many times each name is bound or assigned anywhere in the program.
+    bindings: HashMap<String, usize>,
+    functions: HashMap<String, Inlinable>,
+}
+
+impl Optimiser {
+    fn new(program: &Program, inline: bool) -> Self {
+        let mut bindings = Bindings::default();
+        bindings.visit_block(program);
+        Self {
+            evaluator: Evaluator::new(Rc::new(RefCell::new(Environment::new()))),
+            inline,
+            bindings: bindings.counts,
+            functions: HashMap::new(),
+        }
+    }
+
+    fn program(&mut self, program: Program) -> Program {
+        self.block(program, true)
+    }
+
+    /// Folds the statements of a block, splicing in the branches of `if`
+    /// statements with a literal condition and dropping unreachable ones.
+    /// Blocks don't open a scope, so splicing doesn't change what names mean.
+    fn block(&mut self, block: BlockOfStatements, top_level: bool) -> BlockOfStatements {
+        let mut folded = vec![];
+        let count = block.len();
+        for (i, statement) in block.into_iter().enumerate() {
+            let is_last = i + 1 == count;
+            let statements = match self.fold_statement(statement) {
+                Statement::Expression {
+                    expression:
+                        Expression::If {
+                            condition,
+                            consequence,
+                            alternative,
+                            else_span,
+                            span,
+                        },
+                    span: statement_span,
+                } => match constant(&condition) {
+                    Some(value) => {
+                        let branch = if Evaluator::is_truthy(&value) {
+                            consequence
+                        } else {
+                            alternative.unwrap_or_default()
+                        };
+                        // The block's value comes from its last statement, so an
+                        // empty branch there has to stay to produce nil.
+                        if branch.is_empty() && is_last {
+                            vec![Statement::Expression {
+                                expression: Expression::If {
+                                    condition,
+                                    consequence: vec![],
+                                    alternative: None,
+                                    else_span: None,
+                                    span,
+                                },
+                                span: statement_span,
+                            }]
+                        } else {
+                            branch
+                        }
+                    }
+                    None => vec![Statement::Expression {
+                        expression: Expression::If {
+                            condition,
+                            consequence,
+                            alternative,
+                            else_span,
+                            span,
+                        },
+                        span: statement_span,
+                    }],
+                },
+                statement => vec![statement],
+            };
+
+            for statement in statements {
+                if top_level {
+                    self.register(&statement);
+                }
+                let stops = matches!(
+                    statement,
+                    Statement::Return { .. } | Statement::Break(_) | Statement::Continue(_)
+                );
+                folded.push(statement);
+                if stops {
+                    return folded;
+                }
+            }
+        }
+        folded
+    }
+
+    /// Remembers `let name = func(...) { expression }` when calls to it can be
+    /// replaced by the expression: `name` is never bound again anywhere, and
+    /// the body is small, opens no blocks and reads no names other than its
+    /// parameters and builtins that the program never shadows.
+    fn register(&mut self, statement: &Statement) {
+        let Statement::Let {
+            name,
+            value: Expression::Function { params, body, .. },
+            ..
+        } = statement
+        else {
+            return;
+        };
+        if !self.inline || self.bindings.get(&name.literal) != Some(&1) {
+            return;
+        }
+        let body = match body.as_slice() {
+            [Statement::Expression { expression, .. }]
+            | [Statement::Return {
+                return_value: expression,
+                ..
+            }] => expression,
+            _ => return,
+        };
+        let mut shape = Shape::default();
+        shape.visit_expression(body);
+        let reads_only_params = shape.names.iter().all(|read| {
+            params.iter().any(|param| param.literal == *read)
+                || (is_builtin(read) && !self.bindings.contains_key(read))
+        });
+        let distinct_params = params
+            .iter()
+            .enumerate()
+            .all(|(i, param)| params[..i].iter().all(|p| p.literal != param.literal));
+        if shape.nodes <= MAX_INLINED_NODES
+            && !shape.opens_block
+            && reads_only_params
+            && distinct_params
+        {
+            self.functions.insert(
+                name.literal.clone(),
+                Inlinable {
+                    params: params.clone(),
+                    body: body.clone(),
+                },
+            );
+        }
+    }
+
+    /// Replaces a call with the body of the function it calls, when every
+    /// argument is a literal or a name. Arguments are then evaluated where the
+    /// body reads them, which doesn't matter as they can't have side effects,
+    /// but a name the body might not read would go unchecked, so those calls
+    /// stay.
+    fn inline_call(&self, func: &Expression, args: &[Expression]) -> Option<Expression> {
+        let Expression::Identifier(name) = func else {
+            return None;
+        };
+        let function = self.functions.get(&name.literal)?;
+        if function.params.len() != args.len() {
+            return None;
+        }
+
+        let mut shape = Shape::default();
+        shape.visit_expression(&function.body);
+        let mut substitute = Substitute {
+            args: HashMap::new(),
+        };
+        for (param, arg) in function.params.iter().zip(args) {
+            match arg {
+                Expression::Literal(literal, _) if is_scalar(literal) => {}
+                Expression::Identifier(_)
+                    if shape.names.contains(&param.literal) && !shape.short_circuits => {}
+                _ => return None,
+            }
+            substitute.args.insert(param.literal.clone(), arg.clone());
+        }
+        Some(substitute.fold_expression(function.body.clone()))
+    }
+
+    fn fold_constant(&mut self, expression: Expression) -> Expression {
+        let value = match &expression {
+            Expression::Prefix(prefix, right, _) => match constant(right) {
+                Some(Object::BIGINT(_)) | None => None,
+                Some(right) => self.evaluator.evaluate_prefix(prefix.clone(), right).ok(),
+            },
+            Expression::Infix(operator, left, right, _) => {
+                match (operator, constant(left), constant(right)) {
+                    // `&&` and `||` don't evaluate their right side when the left one
+                    // decides the result.
+                    (Infix::And, Some(left), _) if !Evaluator::is_truthy(&left) => {
+                        Some(Object::BOOL(false))
+                    }
+                    (Infix::Or, Some(left), _) if Evaluator::is_truthy(&left) => {
+                        Some(Object::BOOL(true))
+                    }
+                    (Infix::And | Infix::Or, Some(_), Some(right)) => {
+                        Some(Object::BOOL(Evaluator::is_truthy(&right)))
+                    }
+                    (Infix::And | Infix::Or, _, _) => None,
+                    (operator, Some(left), Some(right)) if fits_in_i64(operator, &left, &right) => {
+                        self.evaluator
+                            .evaluate_infix(operator.clone(), left, right)
+                            .ok()
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match value.and_then(|value| to_literal(value, expression.span())) {
+            Some(literal) => literal,
+            None => expression,
+        }
+    }
+}
+
+impl Folder for Optimiser {
+    fn fold_block(&mut self, block: BlockOfStatements) -> BlockOfStatements {
+        self.block(block, false)
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        match fold::walk_expression(self, expression) {
+            expression @ (Expression::Prefix(..) | Expression::Infix(..)) => {
+                self.fold_constant(expression)
+            }
+            // A branch holding a single expression can stand in for the `if`.
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+                else_span,
+                span,
+            } => {
+                let branch = match constant(&condition) {
+                    Some(value) if Evaluator::is_truthy(&value) => Some(&consequence),
+                    Some(_) => alternative.as_ref(),
+                    None => None,
+                };
+                match branch.map(Vec::as_slice) {
+                    Some([Statement::Expression { expression, .. }]) => expression.clone(),
+                    _ => Expression::If {
+                        condition,
+                        consequence,
+                        alternative,
+                        else_span,
+                        span,
+                    },
+                }
+            }
+            Expression::Call { func, args, span } => {
+                match self.inline_call(&func, args.as_deref().unwrap_or_default()) {
+                    // The body may fold further now that it has its arguments.
+                    Some(body) => self.fold_expression(body),
+                    None => Expression::Call { func, args, span },
+                }
+            }
+            expression => expression,
+        }
+    }
+}
+
+/// The value of a literal number, string or boolean.
+fn constant(expression: &Expression) -> Option<Object> {
+    match expression {
+        Expression::Literal(Literal::Int { value, .. }, _) => Some(Object::INTEGER(*value)),
+        Expression::Literal(Literal::BigInt { value, .. }, _) => Some(Object::from(value.clone())),
+        Expression::Literal(Literal::Float { value, .. }, _) => Some(Object::FLOAT(*value)),
+        Expression::Literal(Literal::String { value, .. }, _) => {
+            Some(Object::STRING(value.clone()))
+        }
+        Expression::Literal(Literal::Bool(b), _) => Some(Object::BOOL(*b)),
+        _ => None,
+    }
+}
+
+/// Whether `left operator right` can be folded without working out a big
+/// integer. Those have no folded form, and some, like `3 ** 400000`, take long
+/// to compute, so they are left to run time.
+fn fits_in_i64(operator: &Infix, left: &Object, right: &Object) -> bool {
+    match (operator, left, right) {
+        (_, Object::BIGINT(_), _) | (_, _, Object::BIGINT(_)) => false,
+        (Infix::Power, Object::INTEGER(base), Object::INTEGER(exponent)) => {
+            u32::try_from(*exponent)
+                .ok()
+                .and_then(|exponent| base.checked_pow(exponent))
+                .is_some()
+        }
+        (Infix::ShiftLeft, Object::INTEGER(value), Object::INTEGER(bits)) => {
+            (0..64).contains(bits) && (value << bits) >> bits == *value
+        }
+        _ => true,
+    }
+}
+
+/// Writes a folded value back as a literal. Big integers have no literal form
+/// here, as their digits can be far longer than the expression.
+fn to_literal(value: Object, span: Span) -> Option<Expression> {
+    // An empty token makes the tree print the value itself.
+    let token = |kind| Token {
+        kind,
+        literal: String::new(),
+        span,
+    };
+    let literal = match value {
+        Object::INTEGER(value) => Literal::Int {
+            token: token(TokenKind::INT),
+            value,
+        },
+        Object::FLOAT(value) => Literal::Float {
+            token: token(TokenKind::FLOAT),
+            value,
+        },
+        Object::STRING(value) if value.len() <= MAX_FOLDED_STRING => Literal::String {
+            token: token(TokenKind::STRING),
+            value,
+        },
+        Object::BOOL(b) => Literal::Bool(b),
+        _ => return None,
+    };
+    Some(Expression::Literal(literal, span))
+}
+
+fn is_scalar(literal: &Literal) -> bool {
+    !matches!(literal, Literal::Array(_) | Literal::Hash(_))
+}
+
+/// Counts the bindings and assignments of each name.
+#[derive(Default)]
+struct Bindings {
+    counts: HashMap<String, usize>,
+}
+
+impl Bindings {
+    fn bind(&mut self, name: &Identifier) {
+        *self.counts.entry(name.literal.clone()).or_default() += 1;
+    }
+}
+
+impl Visitor for Bindings {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { name, .. } => self.bind(name),
+            Statement::For { variable, .. } => self.bind(variable),
+            Statement::Assign { target, .. } => {
+                let mut root = target;
+                while let Expression::Index(left, _, _) = root {
+                    root = left;
+                }
+                if let Expression::Identifier(name) = root {
+                    self.bind(name);
+                }
+            }
+            _ => {}
+        }
+        visit::walk_statement(self, statement)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::Function { params, .. } = expression {
+            for param in params {
+                self.bind(param);
+            }
+        }
+        visit::walk_expression(self, expression)
+    }
+}
+
+/// Measures a function body: its size, the names it reads, whether it holds
+/// statements of its own and whether part of it may not be evaluated.
+#[derive(Default)]
+struct Shape {
+    nodes: usize,
+    names: Vec<String>,
+    opens_block: bool,
+    short_circuits: bool,
+}
+
+impl Visitor for Shape {
+    fn visit_expression(&mut self, expression: &Expression) {
+        self.nodes += 1;
+        match expression {
+            Expression::If { .. } | Expression::Function { .. } => self.opens_block = true,
+            Expression::Infix(Infix::And | Infix::Or, ..) => self.short_circuits = true,
+            _ => {}
+        }
+        visit::walk_expression(self, expression)
+    }
+
+    fn visit_identifier(&mut self, ident: &Identifier) {
+        self.names.push(ident.literal.clone());
+    }
+}
+
+/// Replaces the parameters of an inlined body with the call's arguments.
+struct Substitute {
+    args: HashMap<String, Expression>,
+}
+
+impl Folder for Substitute {
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        match expression {
+            Expression::Identifier(ident) => match self.args.get(&ident.literal) {
+                Some(arg) => arg.clone(),
+                None => Expression::Identifier(ident),
+            },
+            expression => fold::walk_expression(self, expression),
+        }
+    }
+}
+
+#[cfg(test)]
+mod optimiser_test {
+    use super::*;
+    use crate::{
+        abstract_tree::SourceWriter,
+        evaluator::{builltin_funcs::new_builtins, BigInt},
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    fn parse(input: &str) -> Program {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty(), "{:?}", parser.get_errors());
+        program
+    }
+
+    fn optimised(input: &str) -> String {
+        SourceWriter::new().program(&optimise(parse(input)))
+    }
+
+    fn run(program: Program) -> String {
+        let env = Rc::new(RefCell::new(Environment::from(new_builtins())));
+        match Evaluator::new(env).evaluate(program) {
+            Ok(value) => value.to_string(),
+            Err(error) => error.message,
+        }
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        for (input, expected) in [
+            ("2 * 3 + x", "6 + x;\n"),
+            ("-(4 - 6) ** 2", "-4;\n"),
+            ("1.5 * 2 < 4 == !false", "true;\n"),
+            ("\"ab\" + \"c\"", "\"abc\";\n"),
+            ("x * (1 + 2)", "x * 3;\n"),
+            ("false && f()", "false;\n"),
+            ("1 == 1 || f()", "true;\n"),
+            ("true && f()", "true && f();\n"),
+            // Errors and big integers are left for the evaluator.
+            ("x + 1 / 0", "x + 1 / 0;\n"),
+            ("9223372036854775807 + 1", "9223372036854775807 + 1;\n"),
+            ("2 ** 62 + 2 ** 64", "4611686018427387904 + 2 ** 64;\n"),
+            ("1 << 70 >> 70", "1 << 70 >> 70;\n"),
+            ("-99999999999999999999", "-99999999999999999999;\n"),
+            ("\"a\" - 1", "\"a\" - 1;\n"),
+        ] {
+            assert_eq!(optimised(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_big_integers_not_computed() {
+        let (base, exponent) = (Object::INTEGER(3), Object::INTEGER(400_000));
+        assert!(!fits_in_i64(&Infix::Power, &base, &exponent));
+        assert!(!fits_in_i64(&Infix::ShiftLeft, &base, &exponent));
+        assert!(!fits_in_i64(
+            &Infix::Plus,
+            &Object::BIGINT(BigInt::from_i64(1)),
+            &base
+        ));
+        assert!(fits_in_i64(&Infix::Power, &base, &Object::INTEGER(39)));
+    }
+
+    #[test]
+    fn test_dead_code() {
+        for (input, expected) in [
+            ("if (1 < 2) { a } else { b }", "a;\n"),
+            ("let x = if (false) { a } else { b };", "let x = b;\n"),
+            (
+                "if (true) { let y = 1; y } else { z }\nputln(y)",
+                "let y = 1;\ny;\nputln(y);\n",
+            ),
+            ("if (false) { a }\nb", "b;\n"),
+            ("b\nif (false) { a }", "b;\nif false {}\n"),
+            (
+                "func() { a; return 1; b; c }",
+                "func() {\n    a;\n    return 1;\n};\n",
+            ),
+            (
+                "while x { if (true) { break } x -= 1 }",
+                "while x { break }\n",
+            ),
+            ("if (x) { 1 + 1 }", "if x { 2 }\n"),
+        ] {
+            assert_eq!(optimised(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_inlining() {
+        for (input, expected) in [
+            (
+                "let f = func(x) { 2 * 3 + x }; f(1) + f(y)",
+                "let f = func(x) { 6 + x };\n7 + (6 + y);\n",
+            ),
+            (
+                "let sq = func(x) { return x * x }; let g = func(n) { sq(n) + len(\"ab\") }; g(3)",
+                "let sq = func(x) {\n    return x * x;\n};\nlet g = func(n) { n * n + len(\"ab\") };\n9 + len(\"ab\");\n",
+            ),
+            // Calls before the function exists, recursion, rebinding, reading
+            // other names and arguments that could have effects all stay.
+            ("f(1); let f = func(x) { x };", "f(1);\nlet f = func(x) { x };\n"),
+            (
+                "let f = func(n) { f(n) }; f(1)",
+                "let f = func(n) { f(n) };\nf(1);\n",
+            ),
+            (
+                "let f = func(x) { x }; f = g; f(1)",
+                "let f = func(x) { x };\nf = g;\nf(1);\n",
+            ),
+            (
+                "let k = 2; let f = func(x) { x * k }; f(1)",
+                "let k = 2;\nlet f = func(x) { x * k };\nf(1);\n",
+            ),
+            (
+                "let f = func(x) { x }; f(g())",
+                "let f = func(x) { x };\nf(g());\n",
+            ),
+            (
+                "let f = func(x, y) { x }; f(1, y)",
+                "let f = func(x, y) { x };\nf(1, y);\n",
+            ),
+            (
+                "let f = func(x, y) { x || y }; f(true, y)",
+                "let f = func(x, y) { x || y };\nf(true, y);\n",
+            ),
+            (
+                "let len = func(x) { 0 }; let f = func(x) { len(x) }; f(1)",
+                "let len = func(x) { 0 };\nlet f = func(x) { len(x) };\nf(1);\n",
+            ),
+        ] {
+            assert_eq!(optimised(input), expected, "input: {}", input);
+        }
+
+        // The REPL doesn't inline, as later inputs could rebind the function.
+        let program = optimise_input(parse("let f = func(x) { x }; f(1)"));
+        assert_eq!(
+            SourceWriter::new().program(&program),
+            "let f = func(x) { x };\nf(1);\n"
+        );
+    }
+
+    #[test]
+    fn test_same_results() {
+        for input in [
+            "let f = func(x) { 2 * 3 + x }; let g = func(a) { f(a) * f(2) }; g(4)",
+            "let total = 0; let i = 0; while i < 10 { if (i % 2 == 0) { total += i } i += 1 } total",
+            "let f = func(n) { if (n < 2) { return n } return f(n - 1) + f(n - 2); putln(n) }; f(10)",
+            "let x = 1; if (true) { x = 2 } else { x = 3 } x",
+            "let s = func(x) { \"${x}!\" }; s(\"hi\") + s(1.5)",
+            "func() { 5; if (false) { 6 } }()",
+            "let f = func(x) { x / 0 }; f(1)",
+            "-9223372036854775807 - 2",
+        ] {
+            let program = parse(input);
+            assert_eq!(
+                run(optimise(program.clone())),
+                run(program),
+                "input: {}",
+                input
+            );
+        }
+    }
+}
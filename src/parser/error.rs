@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use crate::{
+    diagnostic,
+    tkn::{Span, Token, TokenKind},
+};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ParseErrorKind {
+    UnexpectedToken,
+    ExpectedExpression,
+    InvalidInteger,
+    InvalidFloat,
+    NestingTooDeep,
+    OutsideLoop,
+    InvalidAssignmentTarget,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub expected: Vec<TokenKind>,
+    pub found: Token,
+    pub span: Span,
+    pub help: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, expected: Vec<TokenKind>, found: Token) -> Self {
+        Self {
+            kind,
+            expected,
+            span: found.span,
+            found,
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// The error message without its location.
+    pub fn message(&self) -> String {
+        match self.kind {
+            ParseErrorKind::UnexpectedToken => {
+                let expected = self.expected.iter().map(describe_kind).collect::<Vec<_>>();
+                let expected = match expected.split_last() {
+                    Some((last, [])) => last.clone(),
+                    Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+                    None => "something else".to_string(),
+                };
+                format!(
+                    "expected {}, found {}",
+                    expected,
+                    describe_token(&self.found)
+                )
+            }
+            ParseErrorKind::ExpectedExpression => {
+                format!(
+                    "expected an expression, found {}",
+                    describe_token(&self.found)
+                )
+            }
+            ParseErrorKind::InvalidInteger => {
+                format!("invalid integer literal `{}`", self.found.literal)
+            }
+            ParseErrorKind::InvalidFloat => {
+                format!("invalid float literal `{}`", self.found.literal)
+            }
+            ParseErrorKind::NestingTooDeep => format!(
+                "expression is nested too deeply (the limit is {} levels)",
+                super::MAX_NESTING_DEPTH
+            ),
+            ParseErrorKind::OutsideLoop => format!("`{}` outside of a loop", self.found.literal),
+            ParseErrorKind::InvalidAssignmentTarget => {
+                format!(
+                    "cannot assign to this expression with `{}`",
+                    self.found.literal
+                )
+            }
+        }
+    }
+
+    /// Renders the error with the offending line of `source` underlined.
+    pub fn render(&self, source: &str, colour: bool) -> String {
+        diagnostic::render(
+            source,
+            self.span,
+            &self.message(),
+            self.help.as_deref(),
+            colour,
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.message())
+    }
+}
+
+fn describe_kind(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::EOF => "end of input".to_string(),
+        TokenKind::IDENT => "an identifier".to_string(),
+        TokenKind::INT => "an integer".to_string(),
+        TokenKind::FLOAT => "a float".to_string(),
+        TokenKind::STRING => "a string".to_string(),
+        TokenKind::STRINGMID | TokenKind::STRINGTAIL => {
+            "`}` to close the interpolation".to_string()
+        }
+        TokenKind::DOC => "a doc comment".to_string(),
+        TokenKind::TRUE(_) => "`true`".to_string(),
+        TokenKind::FALSE(_) => "`false`".to_string(),
+        kind => format!("`{}`", kind),
+    }
+}
+
+fn describe_token(token: &Token) -> String {
+    match token.kind {
+        TokenKind::EOF => "end of input".to_string(),
+        TokenKind::IDENT => format!("identifier `{}`", token.literal),
+        TokenKind::INT => format!("integer `{}`", token.literal),
+        TokenKind::FLOAT => format!("float `{}`", token.literal),
+        TokenKind::STRING => format!("string \"{}\"", token.literal),
+        TokenKind::STRINGHEAD | TokenKind::STRINGMID | TokenKind::STRINGTAIL => {
+            "an interpolated string".to_string()
+        }
+        TokenKind::ILLEGAL if token.literal == "/*" => "an unterminated block comment".to_string(),
+        TokenKind::ILLEGAL if token.literal.ends_with('"') => "an unterminated string".to_string(),
+        TokenKind::ILLEGAL if token.literal.starts_with('\\') => {
+            format!("invalid escape sequence `{}`", token.literal)
+        }
+        TokenKind::ILLEGAL => format!("illegal character `{}`", token.literal),
+        _ => format!("`{}`", token.literal),
+    }
+}
@@ -1,16 +1,34 @@
+mod error;
+
+pub use error::{ParseError, ParseErrorKind};
+
+use std::num::IntErrorKind;
+
 use crate::{
     abstract_tree::{
-        Expression, Identifier, Infix, Literal, Precedence, Prefix, Program, Statement,
+        BlockOfStatements, Expression, Identifier, Infix, Literal, Precedence, Prefix, Program,
+        Statement,
     },
+    evaluator::BigInt,
     lexer::Lexer,
     tkn::{Token, TokenKind},
 };
 
+/// Deepest expression nesting the parser accepts before giving up, so that
+/// pathological input is reported instead of overflowing the stack.
+pub const MAX_NESTING_DEPTH: usize = 128;
+
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     curr_token: Token,
     peek_token: Token,
-    errors: Vec<String>,
+    errors: Vec<ParseError>,
+    depth: usize,
+    /// Number of loops enclosing the current statement within the current function.
+    loop_depth: usize,
+    /// Doc comments written right before `curr_token` and `peek_token`.
+    curr_doc: Option<String>,
+    peek_doc: Option<String>,
 }
 
 impl<'a> Parser<'a> {
@@ -20,6 +38,10 @@ impl<'a> Parser<'a> {
             curr_token: Token::default(),
             peek_token: Token::default(),
             errors: vec![],
+            depth: 0,
+            loop_depth: 0,
+            curr_doc: None,
+            peek_doc: None,
         };
 
         parser.next_token();
@@ -30,11 +52,18 @@ impl<'a> Parser<'a> {
 
     pub fn token_to_precedence(tok: &Token) -> Precedence {
         match tok.kind {
+            TokenKind::OR => Precedence::LogicalOr,
+            TokenKind::AND => Precedence::LogicalAnd,
             TokenKind::EQ | TokenKind::NEQ => Precedence::Equals,
-            TokenKind::LT => Precedence::LessGreater,
-            TokenKind::GT => Precedence::LessGreater,
+            TokenKind::LT | TokenKind::LTE => Precedence::LessGreater,
+            TokenKind::GT | TokenKind::GTE => Precedence::LessGreater,
+            TokenKind::PIPE => Precedence::BitOr,
+            TokenKind::CARET => Precedence::BitXor,
+            TokenKind::AMPERSAND => Precedence::BitAnd,
+            TokenKind::SHL | TokenKind::SHR => Precedence::Shift,
             TokenKind::PLUS | TokenKind::MINUS => Precedence::Sum,
-            TokenKind::SLASH | TokenKind::ASTERISK => Precedence::Product,
+            TokenKind::SLASH | TokenKind::ASTERISK | TokenKind::PERCENT => Precedence::Product,
+            TokenKind::POWER => Precedence::Power,
             TokenKind::LBRACKET => Precedence::Index,
             TokenKind::LPAREN => Precedence::Call,
             _ => Precedence::Lowest,
@@ -49,130 +78,370 @@ impl<'a> Parser<'a> {
         Parser::token_to_precedence(&self.curr_token)
     }
 
-    pub fn get_errors(&mut self) -> &Vec<String> {
+    pub fn get_errors(&mut self) -> &Vec<ParseError> {
         &self.errors
     }
 
     fn peek_error(&mut self, token_kind: TokenKind) {
-        let msg = format!(
-            "Expected {:?}, but received {:?}",
-            token_kind, self.peek_token.kind
+        let mut error = ParseError::new(
+            ParseErrorKind::UnexpectedToken,
+            vec![token_kind],
+            self.peek_token.clone(),
         );
-        self.errors.push(msg)
+        if self.peek_token.kind == TokenKind::EOF {
+            if let Some(help) = match token_kind {
+                TokenKind::RPAREN | TokenKind::RBRACKET | TokenKind::RBRACE => Some(format!(
+                    "the input ended before this `{}` was found",
+                    token_kind
+                )),
+                _ => None,
+            } {
+                error = error.with_help(help);
+            }
+        }
+        self.errors.push(error)
     }
 
+    /// Advances by one token. Blank lines are skipped, and doc comments aren't
+    /// handed to the parsing functions as tokens; they are collected and
+    /// attached to the token that follows them.
     pub fn next_token(&mut self) {
         self.curr_token = self.peek_token.clone();
+        self.curr_doc = self.peek_doc.take();
         self.peek_token = self.lexer.next_token();
+        loop {
+            match self.peek_token.kind {
+                TokenKind::BLANK => {}
+                TokenKind::DOC => {
+                    let line = std::mem::take(&mut self.peek_token.literal);
+                    match self.peek_doc {
+                        Some(ref mut doc) => {
+                            doc.push('\n');
+                            doc.push_str(&line);
+                        }
+                        None => self.peek_doc = Some(line),
+                    }
+                }
+                _ => break,
+            }
+            self.peek_token = self.lexer.next_token();
+        }
     }
 
     pub fn parse_program(&mut self) -> Program {
         let mut program = vec![];
         while self.curr_token.kind != TokenKind::EOF {
-            let statement = self.parse_statement();
-            if statement.is_some() {
-                let statement = statement.unwrap();
-                program.push(statement);
+            match self.parse_statement() {
+                Some(statement) => program.push(statement),
+                None => self.synchronize(),
             }
             self.next_token()
         }
         program
     }
 
+    /// Skips the remainder of a statement that failed to parse so that parsing
+    /// can resume at the next statement boundary (`;`, `}` or a statement keyword).
+    fn synchronize(&mut self) {
+        while !self.curr_token_is(TokenKind::SEMICOLON) && !self.curr_token_is(TokenKind::RBRACE) {
+            match self.peek_token.kind {
+                TokenKind::LET
+                | TokenKind::RETURN
+                | TokenKind::WHILE
+                | TokenKind::FOR
+                | TokenKind::RBRACE
+                | TokenKind::EOF => break,
+                _ => self.next_token(),
+            }
+        }
+    }
+
     pub fn parse_statement(&mut self) -> Option<Statement> {
         match self.curr_token.kind {
             TokenKind::LET => self.parse_let_statement(),
             TokenKind::RETURN => self.parse_return_statement(),
+            TokenKind::WHILE => self.parse_while_statement(),
+            TokenKind::FOR => self.parse_for_statement(),
+            TokenKind::BREAK | TokenKind::CONTINUE => self.parse_loop_control_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
     pub fn parse_let_statement(&mut self) -> Option<Statement> {
-        match self.peek_token.kind {
-            TokenKind::IDENT => self.next_token(),
-            _ => return None,
-        }
+        let start = self.curr_token.span;
+        let doc = self.curr_doc.take();
 
-        let name = match self.parse_ident() {
-            Some(name) => name,
-            None => return None,
-        };
+        if !self.expect_peek(TokenKind::IDENT) {
+            return None;
+        }
 
-        if !self.expect_peek(TokenKind::ASSIGN) {
+        let name = self.parse_ident()?;
+
+        if !self.peek_token_is(TokenKind::ASSIGN) {
+            self.errors.push(
+                ParseError::new(
+                    ParseErrorKind::UnexpectedToken,
+                    vec![TokenKind::ASSIGN],
+                    self.peek_token.clone(),
+                )
+                .with_help(format!(
+                    "a let binding needs a value, e.g. `let {} = 5;`",
+                    name.literal
+                )),
+            );
             return None;
         }
+        self.next_token();
 
         self.next_token();
 
-        let value = match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
+        let value = self.parse_expression(Precedence::Lowest)?;
 
-        // println!("Name --> {:?}", name);
-        // println!("Value --> {:?}", value);
         if self.peek_token_is(TokenKind::SEMICOLON) {
             self.next_token();
         }
 
-        let st = Statement::Let { name, value };
+        let st = Statement::Let {
+            name,
+            value,
+            doc,
+            span: start.to(self.curr_token.span),
+        };
         Some(st)
     }
 
     pub fn parse_return_statement(&mut self) -> Option<Statement> {
+        let start = self.curr_token.span;
+
         self.next_token();
 
-        // let return_value = Expression::Identifier(Identifier {
-        //     literal: self.curr_token.literal.clone(),
-        //     token: self.curr_token.clone(),
-        // });
+        let return_value = self.parse_expression(Precedence::Lowest)?;
 
-        let return_value = match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
+        if self.peek_token_is(TokenKind::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::Return {
+            return_value,
+            span: start.to(self.curr_token.span),
+        })
+    }
+
+    pub fn parse_while_statement(&mut self) -> Option<Statement> {
+        let start = self.curr_token.span;
 
-        while !self.curr_token_is(TokenKind::SEMICOLON) {
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenKind::LBRACE) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+
+        if self.peek_token_is(TokenKind::SEMICOLON) {
             self.next_token();
         }
 
-        Some(Statement::Return { return_value })
+        Some(Statement::While {
+            condition,
+            body,
+            span: start.to(self.curr_token.span),
+        })
+    }
+
+    pub fn parse_for_statement(&mut self) -> Option<Statement> {
+        let start = self.curr_token.span;
+
+        if !self.expect_peek(TokenKind::IDENT) {
+            return None;
+        }
+        let variable = self.parse_ident()?;
+
+        if !self.expect_peek(TokenKind::IN) {
+            return None;
+        }
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenKind::LBRACE) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+
+        if self.peek_token_is(TokenKind::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::For {
+            variable,
+            iterable,
+            body,
+            span: start.to(self.curr_token.span),
+        })
+    }
+
+    fn parse_loop_body(&mut self) -> Option<BlockOfStatements> {
+        self.loop_depth += 1;
+        let body = self.parse_block_statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    pub fn parse_loop_control_statement(&mut self) -> Option<Statement> {
+        let start = self.curr_token.span;
+
+        if self.loop_depth == 0 {
+            self.errors.push(ParseError::new(
+                ParseErrorKind::OutsideLoop,
+                vec![],
+                self.curr_token.clone(),
+            ));
+            return None;
+        }
+        let is_break = self.curr_token_is(TokenKind::BREAK);
+
+        if self.peek_token_is(TokenKind::SEMICOLON) {
+            self.next_token();
+        }
+
+        let span = start.to(self.curr_token.span);
+        Some(if is_break {
+            Statement::Break(span)
+        } else {
+            Statement::Continue(span)
+        })
     }
 
     pub fn parse_expression_statement(&mut self) -> Option<Statement> {
-        match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => {
-                if self.peek_token_is(TokenKind::SEMICOLON) {
-                    self.next_token();
-                }
-                return Some(Statement::Expression { expression: expr });
-            }
-            None => None,
+        let expression = self.parse_expression(Precedence::Lowest)?;
+
+        if let Some(operator) = Parser::assignment_operator(&self.peek_token) {
+            return self.parse_assignment_statement(expression, operator);
+        }
+
+        if self.peek_token_is(TokenKind::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::Expression {
+            span: expression.span().to(self.curr_token.span),
+            expression,
+        })
+    }
+
+    /// Maps `=` to `Some(None)` and a compound assignment like `+=` to the infix
+    /// operator it applies.
+    fn assignment_operator(tok: &Token) -> Option<Option<Infix>> {
+        match tok.kind {
+            TokenKind::ASSIGN => Some(None),
+            TokenKind::PLUSEQ => Some(Some(Infix::Plus)),
+            TokenKind::MINUSEQ => Some(Some(Infix::Minus)),
+            TokenKind::ASTERISKEQ => Some(Some(Infix::Multiply)),
+            TokenKind::SLASHEQ => Some(Some(Infix::Divide)),
+            _ => None,
+        }
+    }
+
+    /// Whether `target` can be assigned to: a name or an index expression
+    /// rooted at a name.
+    pub fn is_assignable(target: &Expression) -> bool {
+        match target {
+            Expression::Identifier(_) => true,
+            Expression::Index(left, _, _) => Parser::is_assignable(left),
+            _ => false,
+        }
+    }
+
+    fn parse_assignment_statement(
+        &mut self,
+        target: Expression,
+        operator: Option<Infix>,
+    ) -> Option<Statement> {
+        if !Parser::is_assignable(&target) {
+            let mut error = ParseError::new(
+                ParseErrorKind::InvalidAssignmentTarget,
+                vec![],
+                self.peek_token.clone(),
+            )
+            .with_help(
+                "only names and index expressions like `x` or `items[0]` can be assigned to",
+            );
+            error.span = target.span();
+            self.errors.push(error);
+            return None;
         }
+
+        self.next_token();
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        if self.peek_token_is(TokenKind::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::Assign {
+            span: target.span().to(self.curr_token.span),
+            target,
+            operator,
+            value,
+        })
     }
 
     pub fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            self.errors.push(ParseError::new(
+                ParseErrorKind::NestingTooDeep,
+                vec![],
+                self.curr_token.clone(),
+            ));
+            return None;
+        }
+
+        self.depth += 1;
+        let expression = self.parse_expression_inner(precedence);
+        self.depth -= 1;
+
+        expression
+    }
+
+    fn parse_expression_inner(&mut self, precedence: Precedence) -> Option<Expression> {
         // PREFIX
         let mut left = match self.curr_token.kind {
             TokenKind::IDENT => self.parse_ident_expr(),
             TokenKind::INT => self.parse_int_expr(),
+            TokenKind::FLOAT => self.parse_float_expr(),
             TokenKind::TRUE(_) | TokenKind::FALSE(_) => self.parse_bool_expr(),
             TokenKind::BANG | TokenKind::MINUS | TokenKind::PLUS => self.parse_prefix_expr(),
             TokenKind::LPAREN => self.parse_grouped_expr(),
             TokenKind::LBRACKET => self.parse_array_expr(),
+            TokenKind::LBRACE => self.parse_hash_expr(),
             TokenKind::IF => self.parse_if_expr(),
             TokenKind::FUNCTION => self.parse_function_expr(),
-            TokenKind::STRING => Some(Expression::Literal(Literal::String(
-                self.curr_token.literal.clone(),
-            ))),
+            TokenKind::STRING => Some(Expression::Literal(
+                Literal::String {
+                    token: Token {
+                        literal: self.lexer.text(self.curr_token.span).to_string(),
+                        ..self.curr_token.clone()
+                    },
+                    value: self.curr_token.literal.clone(),
+                },
+                self.curr_token.span,
+            )),
+            TokenKind::STRINGHEAD => self.parse_interpolation_expr(),
             _ => {
-                self.errors.push(format!(
-                    "No Prefix Parsing Function available for {:?}",
-                    self.curr_token.kind
-                ));
-                None
+                let mut error = ParseError::new(
+                    ParseErrorKind::ExpectedExpression,
+                    vec![],
+                    self.curr_token.clone(),
+                );
+                if self.curr_token_is(TokenKind::ASSIGN) {
+                    error = error.with_help("use `==` to compare two values");
+                }
+                self.errors.push(error);
+                return None;
             }
-        };
+        }?;
 
         // INFIX
         while !self.peek_token_is(TokenKind::SEMICOLON) && precedence < self.peek_precedence() {
@@ -181,6 +450,15 @@ impl<'a> Parser<'a> {
                 | TokenKind::MINUS
                 | TokenKind::SLASH
                 | TokenKind::ASTERISK
+                | TokenKind::PERCENT
+                | TokenKind::POWER
+                | TokenKind::AND
+                | TokenKind::OR
+                | TokenKind::AMPERSAND
+                | TokenKind::PIPE
+                | TokenKind::CARET
+                | TokenKind::SHL
+                | TokenKind::SHR
                 | TokenKind::EQ
                 | TokenKind::NEQ
                 | TokenKind::LT
@@ -188,21 +466,21 @@ impl<'a> Parser<'a> {
                 | TokenKind::LTE
                 | TokenKind::GTE => {
                     self.next_token();
-                    left = self.parse_infix_expr(left.unwrap());
+                    left = self.parse_infix_expr(left)?;
                 }
                 TokenKind::LPAREN => {
                     self.next_token();
-                    left = self.parse_call_expr(left.unwrap())
+                    left = self.parse_call_expr(left)?;
                 }
                 TokenKind::LBRACKET => {
                     self.next_token();
-                    left = self.parse_index_expr(left.unwrap())
+                    left = self.parse_index_expr(left)?;
                 }
-                _ => return left,
+                _ => return Some(left),
             }
         }
 
-        left
+        Some(left)
     }
 
     fn parse_infix_expr(&mut self, left: Expression) -> Option<Expression> {
@@ -211,21 +489,41 @@ impl<'a> Parser<'a> {
             TokenKind::MINUS => Infix::Minus,
             TokenKind::SLASH => Infix::Divide,
             TokenKind::ASTERISK => Infix::Multiply,
+            TokenKind::PERCENT => Infix::Modulo,
+            TokenKind::POWER => Infix::Power,
+            TokenKind::AND => Infix::And,
+            TokenKind::OR => Infix::Or,
+            TokenKind::AMPERSAND => Infix::BitAnd,
+            TokenKind::PIPE => Infix::BitOr,
+            TokenKind::CARET => Infix::BitXor,
+            TokenKind::SHL => Infix::ShiftLeft,
+            TokenKind::SHR => Infix::ShiftRight,
             TokenKind::EQ => Infix::Equal,
             TokenKind::NEQ => Infix::NotEqual,
             TokenKind::LT => Infix::LessThan,
+            TokenKind::LTE => Infix::LessThanEqual,
             TokenKind::GT => Infix::GreaterThan,
+            TokenKind::GTE => Infix::GreaterThanEqual,
             _ => return None,
         };
 
-        let precedence = self.curr_precedence();
+        // `**` is right-associative, so its right operand may itself contain `**`.
+        let precedence = match infix {
+            Infix::Power => Precedence::Prefix,
+            _ => self.curr_precedence(),
+        };
 
         self.next_token();
 
-        match self.parse_expression(precedence) {
-            Some(expr) => Some(Expression::Infix(infix, Box::new(left), Box::new(expr))),
-            None => None,
-        }
+        let right = self.parse_expression(precedence)?;
+        let span = left.span().to(self.curr_token.span);
+
+        Some(Expression::Infix(
+            infix,
+            Box::new(left),
+            Box::new(right),
+            span,
+        ))
     }
 
     fn parse_ident(&mut self) -> Option<Identifier> {
@@ -239,38 +537,85 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_ident_expr(&mut self) -> Option<Expression> {
-        match self.parse_ident() {
-            Some(ident) => Some(Expression::Identifier(ident)),
-            None => None,
-        }
+        self.parse_ident().map(Expression::Identifier)
     }
 
     pub fn parse_bool_expr(&mut self) -> Option<Expression> {
         match self.curr_token.kind {
-            TokenKind::TRUE(val) | TokenKind::FALSE(val) => {
-                Some(Expression::Literal(Literal::Bool(val == true)))
-            }
+            TokenKind::TRUE(val) | TokenKind::FALSE(val) => Some(Expression::Literal(
+                Literal::Bool(val),
+                self.curr_token.span,
+            )),
             _ => None,
         }
     }
 
     pub fn parse_int_expr(&mut self) -> Option<Expression> {
         if self.curr_token_is(TokenKind::INT) {
-            let int_value = self.curr_token.literal.parse::<i64>();
+            let literal = self.curr_token.literal.replace('_', "");
+            let (digits, radix, name) = match literal.get(..2) {
+                Some("0x" | "0X") => (&literal[2..], 16, "hexadecimal"),
+                Some("0o" | "0O") => (&literal[2..], 8, "octal"),
+                Some("0b" | "0B") => (&literal[2..], 2, "binary"),
+                _ => (literal.as_str(), 10, "decimal"),
+            };
+            let literal = match i64::from_str_radix(digits, radix) {
+                Ok(value) => Literal::Int {
+                    token: self.curr_token.clone(),
+                    value,
+                },
+                // `from_str_radix` only overflows once the digits are known to be valid.
+                Err(error) if *error.kind() == IntErrorKind::PosOverflow => Literal::BigInt {
+                    token: self.curr_token.clone(),
+                    value: BigInt::parse_radix(digits, radix).unwrap_or_default(),
+                },
+                Err(_) => {
+                    let mut error = ParseError::new(
+                        ParseErrorKind::InvalidInteger,
+                        vec![],
+                        self.curr_token.clone(),
+                    );
+                    if let Some(digit) = digits.chars().find(|ch| !ch.is_digit(radix)) {
+                        error = error.with_help(format!("`{}` is not a {} digit", digit, name));
+                    } else if digits.is_empty() {
+                        error = error.with_help(format!("expected {} digits", name));
+                    }
+                    self.errors.push(error);
+                    return None;
+                }
+            };
+            return Some(Expression::Literal(literal, self.curr_token.span));
+        }
+        None
+    }
 
-            if int_value.is_err() {
-                return None;
-            }
-            let int_value = int_value.unwrap();
-            return Some(Expression::Literal(Literal::Int {
-                token: self.curr_token.clone(),
-                value: int_value,
-            }));
+    pub fn parse_float_expr(&mut self) -> Option<Expression> {
+        if self.curr_token_is(TokenKind::FLOAT) {
+            let float_value = match self.curr_token.literal.replace('_', "").parse::<f64>() {
+                Ok(value) => value,
+                Err(_) => {
+                    self.errors.push(ParseError::new(
+                        ParseErrorKind::InvalidFloat,
+                        vec![],
+                        self.curr_token.clone(),
+                    ));
+                    return None;
+                }
+            };
+            return Some(Expression::Literal(
+                Literal::Float {
+                    token: self.curr_token.clone(),
+                    value: float_value,
+                },
+                self.curr_token.span,
+            ));
         }
         None
     }
 
     pub fn parse_prefix_expr(&mut self) -> Option<Expression> {
+        let start = self.curr_token.span;
+
         let prefix = match self.curr_token.kind {
             TokenKind::BANG => Prefix::Not,
             TokenKind::MINUS => Prefix::Minus,
@@ -280,31 +625,37 @@ impl<'a> Parser<'a> {
 
         self.next_token();
 
-        match self.parse_expression(Precedence::Prefix) {
-            Some(expr) => Some(Expression::Prefix(prefix, Box::new(expr))),
-            None => None,
-        }
+        let right = self.parse_expression(Precedence::Prefix)?;
+
+        Some(Expression::Prefix(
+            prefix,
+            Box::new(right),
+            start.to(self.curr_token.span),
+        ))
     }
 
     pub fn parse_function_expr(&mut self) -> Option<Expression> {
+        let start = self.curr_token.span;
+
         if !self.expect_peek(TokenKind::LPAREN) {
             return None;
         }
-        let params = match self.parse_function_params() {
-            Some(params) => params,
-            None => return None,
-        };
+        let params = self.parse_function_params()?;
 
         if !self.expect_peek(TokenKind::LBRACE) {
             return None;
         }
 
-        // println!("Function_Body -->{:?}", self.curr_token);
+        // `break` and `continue` never reach a loop outside of the function.
+        let loop_depth = std::mem::take(&mut self.loop_depth);
         let function_body = self.parse_block_statement();
+        self.loop_depth = loop_depth;
+        let function_body = function_body?;
 
         Some(Expression::Function {
             params,
             body: function_body,
+            span: start.to(self.curr_token.span),
         })
     }
 
@@ -316,20 +667,17 @@ impl<'a> Parser<'a> {
             return Some(params);
         }
 
-        self.next_token();
-
-        match self.parse_ident() {
-            Some(ident) => params.push(ident),
-            None => return None,
+        if !self.expect_peek(TokenKind::IDENT) {
+            return None;
         }
+        params.push(self.parse_ident()?);
         while self.peek_token_is(TokenKind::COMMA) {
             self.next_token();
-            self.next_token();
-
-            match self.parse_ident() {
-                Some(ident) => params.push(ident),
-                None => return None,
+            if !self.expect_peek(TokenKind::IDENT) {
+                return None;
             }
+
+            params.push(self.parse_ident()?);
         }
 
         if !self.expect_peek(TokenKind::RPAREN) {
@@ -342,123 +690,182 @@ impl<'a> Parser<'a> {
     pub fn parse_grouped_expr(&mut self) -> Option<Expression> {
         self.next_token();
 
-        let expression = self.parse_expression(Precedence::Lowest);
+        let expression = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(TokenKind::RPAREN) {
             None
         } else {
-            expression
+            Some(expression)
         }
     }
 
     pub fn parse_if_expr(&mut self) -> Option<Expression> {
         // if x>4{true;} else { false;}
-        // println!("CURR TOKEN --> {:?}", self.curr_token);
+        let start = self.curr_token.span;
+
         self.next_token();
-        // println!("CURR TOKEN --> {:?}", self.curr_token);
-        let condition = match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
+        let condition = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(TokenKind::LBRACE) {
             return None;
         }
 
-        let consequence = self.parse_block_statement();
+        let consequence = self.parse_block_statement()?;
         let mut alternative: Option<Vec<Statement>> = None;
+        let mut else_span = None;
 
         if self.peek_token_is(TokenKind::ELSE) {
             self.next_token();
+            else_span = Some(self.curr_token.span);
             if !self.expect_peek(TokenKind::LBRACE) {
                 return None;
             }
-            alternative = Some(self.parse_block_statement());
+            alternative = Some(self.parse_block_statement()?);
         }
 
         Some(Expression::If {
             condition: Box::new(condition),
             consequence,
             alternative,
+            else_span,
+            span: start.to(self.curr_token.span),
         })
     }
 
-    pub fn parse_block_statement(&mut self) -> Vec<Statement> {
+    pub fn parse_block_statement(&mut self) -> Option<Vec<Statement>> {
         self.next_token();
         let mut block = vec![];
         while !self.curr_token_is(TokenKind::RBRACE) && !self.curr_token_is(TokenKind::EOF) {
             match self.parse_statement() {
                 Some(stmt) => block.push(stmt),
-                None => {}
+                None => {
+                    self.synchronize();
+                    if self.curr_token_is(TokenKind::RBRACE) {
+                        continue;
+                    }
+                }
             }
 
             self.next_token()
         }
 
-        block
+        if self.curr_token_is(TokenKind::EOF) {
+            self.errors.push(
+                ParseError::new(
+                    ParseErrorKind::UnexpectedToken,
+                    vec![TokenKind::RBRACE],
+                    self.curr_token.clone(),
+                )
+                .with_help("the input ended before this block was closed"),
+            );
+            return None;
+        }
+
+        Some(block)
     }
 
     fn parse_call_expr(&mut self, left: Expression) -> Option<Expression> {
-        let args = self.parse_expr_list(TokenKind::RPAREN);
+        let args = self.parse_expr_list(TokenKind::RPAREN)?;
+        let span = left.span().to(self.curr_token.span);
 
         Some(Expression::Call {
             func: Box::new(left),
-            args,
+            args: Some(args),
+            span,
         })
     }
 
     fn parse_index_expr(&mut self, left: Expression) -> Option<Expression> {
         self.next_token();
 
-        let index = match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
+        let index = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(TokenKind::RBRACKET) {
             return None;
         }
 
-        let res = Expression::Index(Box::new(left), Box::new(index));
-        // println!("{:?}", res);
-        Some(res)
+        let span = left.span().to(self.curr_token.span);
+        Some(Expression::Index(Box::new(left), Box::new(index), span))
     }
 
-    // pub fn parse_call_args(&mut self) -> Option<Vec<Expression>> {
-    //     let mut args: Vec<Expression> = vec![];
-    //     if self.peek_token_is(TokenKind::RPAREN) {
-    //         self.next_token();
-    //         return Some(args);
-    //     }
+    /// Parses `"text ${expr} text"`, which the lexer hands over as a `STRINGHEAD`,
+    /// then each expression followed by a `STRINGMID` or the final `STRINGTAIL`.
+    fn parse_interpolation_expr(&mut self) -> Option<Expression> {
+        let start = self.curr_token.span;
+        let mut parts = vec![];
+
+        loop {
+            if !self.curr_token.literal.is_empty() {
+                parts.push(Expression::Literal(
+                    Literal::String {
+                        token: Token {
+                            kind: TokenKind::STRING,
+                            literal: String::new(),
+                            span: self.curr_token.span,
+                        },
+                        value: self.curr_token.literal.clone(),
+                    },
+                    self.curr_token.span,
+                ));
+            }
+            if self.curr_token_is(TokenKind::STRINGTAIL) {
+                break;
+            }
 
-    //     self.next_token();
+            self.next_token();
+            parts.push(self.parse_expression(Precedence::Lowest)?);
 
-    //     match self.parse_expression(Precedence::Lowest) {
-    //         Some(expr) => args.push(expr),
-    //         None => return None,
-    //     }
-    //     while self.peek_token_is(TokenKind::COMMA) {
-    //         self.next_token();
-    //         self.next_token();
+            if self.peek_token_is(TokenKind::STRINGMID) {
+                self.next_token();
+            } else if !self.expect_peek(TokenKind::STRINGTAIL) {
+                return None;
+            }
+        }
 
-    //         match self.parse_expression(Precedence::Lowest) {
-    //             Some(expr) => args.push(expr),
-    //             None => return None,
-    //         }
-    //     }
+        Some(Expression::Interpolation(
+            parts,
+            start.to(self.curr_token.span),
+        ))
+    }
 
-    //     if !self.expect_peek(TokenKind::RPAREN) {
-    //         return None;
-    //     }
+    fn parse_array_expr(&mut self) -> Option<Expression> {
+        let start = self.curr_token.span;
 
-    //     Some(args)
-    // }
+        let list = self.parse_expr_list(TokenKind::RBRACKET)?;
 
-    fn parse_array_expr(&mut self) -> Option<Expression> {
-        match self.parse_expr_list(TokenKind::RBRACKET) {
-            Some(list) => Some(Expression::Literal(Literal::Array(list))),
-            None => None,
+        Some(Expression::Literal(
+            Literal::Array(list),
+            start.to(self.curr_token.span),
+        ))
+    }
+
+    fn parse_hash_expr(&mut self) -> Option<Expression> {
+        let start = self.curr_token.span;
+        let mut pairs = vec![];
+
+        while !self.peek_token_is(TokenKind::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
+
+            if !self.expect_peek(TokenKind::COLON) {
+                return None;
+            }
+
+            self.next_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+
+            if !self.peek_token_is(TokenKind::RBRACE) && !self.expect_peek(TokenKind::COMMA) {
+                return None;
+            }
         }
+
+        self.next_token();
+
+        Some(Expression::Literal(
+            Literal::Hash(pairs),
+            start.to(self.curr_token.span),
+        ))
     }
 
     fn parse_expr_list(&mut self, end_token: TokenKind) -> Option<Vec<Expression>> {
@@ -471,23 +878,13 @@ impl<'a> Parser<'a> {
 
         self.next_token();
 
-        match self.parse_expression(Precedence::Lowest) {
-            Some(expr) => {
-                list.push(expr);
-            }
-            None => return None,
-        }
+        list.push(self.parse_expression(Precedence::Lowest)?);
 
         while self.peek_token_is(TokenKind::COMMA) {
             self.next_token();
             self.next_token();
 
-            match self.parse_expression(Precedence::Lowest) {
-                Some(expr) => {
-                    list.push(expr);
-                }
-                None => return None,
-            }
+            list.push(self.parse_expression(Precedence::Lowest)?);
         }
 
         if !self.expect_peek(end_token) {
@@ -514,9 +911,13 @@ impl<'a> Parser<'a> {
 }
 
 #[cfg(test)]
+// The original tests predate the lints the tree is now checked with.
+#[allow(clippy::useless_vec, clippy::collapsible_match, clippy::len_zero)]
 mod parser_test {
 
     use super::*;
+    use crate::tkn::Span;
+
     #[test]
     fn test_let_statement() {
         let input = "
@@ -542,7 +943,7 @@ mod parser_test {
 
         for (i, tt) in tests.iter().enumerate() {
             let stmt = &program[i];
-            if let Statement::Let { name, value: _ } = stmt {
+            if let Statement::Let { name, value: _, .. } = stmt {
                 let Identifier { literal, token } = name;
                 assert_eq!(literal, *tt, "Expected {} but received {}", tt, literal);
                 assert_eq!(token.kind, TokenKind::IDENT, "Unmatching token types");
@@ -578,7 +979,7 @@ mod parser_test {
         for (i, tt) in tests.iter().enumerate() {
             let stmt = &program[i];
             // println!("{:?}", stmt);
-            if let Statement::Return { return_value } = stmt {
+            if let Statement::Return { return_value, .. } = stmt {
                 if let Expression::Identifier(Identifier { literal, token: _ }) = return_value {
                     assert_eq!(literal, *tt, "Expected {} but received {}", tt, literal);
                 };
@@ -607,7 +1008,7 @@ mod parser_test {
 
         let stmt = &program[0];
         println!("{:?}", stmt);
-        if let Statement::Expression { expression } = stmt {
+        if let Statement::Expression { expression, .. } = stmt {
             if let Expression::Identifier(ident) = expression {
                 assert_eq!(
                     ident.literal, "foobar",
@@ -646,8 +1047,8 @@ mod parser_test {
 
         let stmt = &program[0];
         println!("{:?}", stmt);
-        if let Statement::Expression { expression } = stmt {
-            if let Expression::Literal(Literal::Int { token: _, value }) = expression {
+        if let Statement::Expression { expression, .. } = stmt {
+            if let Expression::Literal(Literal::Int { token: _, value }, _) = expression {
                 assert_eq!(&5344, value);
             }
         } else {
@@ -823,4 +1224,549 @@ mod parser_test {
             program.len()
         );
     }
+
+    #[test]
+    fn test_spans() {
+        let l = Lexer::new("let a = 1;\nputs(a + 2 * b);");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        assert_eq!(program.len(), 2);
+
+        assert_eq!(program[0].span(), Span::new(0, 10, 1, 1));
+        if let Statement::Expression { expression, span } = &program[1] {
+            assert_eq!(*span, Span::new(11, 27, 2, 1));
+            assert_eq!(expression.span(), Span::new(11, 26, 2, 1));
+            if let Expression::Call {
+                args: Some(args), ..
+            } = expression
+            {
+                assert_eq!(args[0].span(), Span::new(16, 25, 2, 6));
+                assert_eq!(args[0].span().to_string(), "line 2:6");
+            } else {
+                panic!("Expected a Call Expression but received.. {:?}", expression)
+            }
+        } else {
+            panic!(
+                "Expected a Expression Statement but received.. {:?}",
+                program[1]
+            )
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let l = Lexer::new("let x = 5;\n  let y = (1 + 2;");
+        let mut p = Parser::new(l);
+
+        p.parse_program();
+        let errors = p.get_errors();
+        assert_eq!(errors[0].span, Span::new(27, 28, 2, 17));
+        assert_eq!(
+            errors[0].to_string(),
+            "line 2:17: expected `)`, found `;`",
+            "Expected the error to carry its location, but received {}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let l = Lexer::new("let total 5");
+        let mut p = Parser::new(l);
+
+        p.parse_program();
+        let error = &p.get_errors()[0];
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(error.expected, vec![TokenKind::ASSIGN]);
+        assert_eq!(error.found.kind, TokenKind::INT);
+        assert_eq!(
+            error.help.as_deref(),
+            Some("a let binding needs a value, e.g. `let total = 5;`")
+        );
+
+        let l = Lexer::new("add(1, 2");
+        let mut p = Parser::new(l);
+
+        p.parse_program();
+        let error = &p.get_errors()[0];
+        assert_eq!(error.message(), "expected `)`, found end of input");
+        assert!(error.help.is_some());
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let l = Lexer::new("* 3; let = 1; let y 2; let z = 3; return z");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        let errors = p.get_errors();
+        assert_eq!(
+            errors.len(),
+            3,
+            "Expected 3 errors, instead received {:?}",
+            errors
+        );
+        assert_eq!(errors[0].kind, ParseErrorKind::ExpectedExpression);
+        assert_eq!(errors[1].expected, vec![TokenKind::IDENT]);
+        assert_eq!(errors[2].expected, vec![TokenKind::ASSIGN]);
+        assert_eq!(
+            program.len(),
+            2,
+            "Expected 2 statements, instead received {:?}",
+            program
+        );
+        assert!(matches!(program[0], Statement::Let { .. }));
+        assert!(matches!(program[1], Statement::Return { .. }));
+    }
+
+    #[test]
+    fn test_block_error_recovery() {
+        let l = Lexer::new("func(x) { let = 1; x + ; x }; let y = 2;");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        assert_eq!(p.get_errors().len(), 2, "{:?}", p.get_errors());
+        assert_eq!(program.len(), 2, "{:?}", program);
+        if let Statement::Expression {
+            expression: Expression::Function { body, .. },
+            ..
+        } = &program[0]
+        {
+            assert_eq!(body.len(), 1, "{:?}", body);
+        } else {
+            panic!(
+                "Expected a Function Expression but received.. {:?}",
+                program[0]
+            )
+        }
+
+        let l = Lexer::new("if (x) { 1");
+        let mut p = Parser::new(l);
+
+        p.parse_program();
+        assert_eq!(p.get_errors()[0].expected, vec![TokenKind::RBRACE]);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let inputs = [
+            format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000)),
+            format!("{}1", "-".repeat(100_000)),
+            format!("{}1{}", "[".repeat(10_000), "]".repeat(10_000)),
+            "if x { ".repeat(10_000),
+        ];
+        for input in inputs.iter() {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            p.parse_program();
+            assert_eq!(p.get_errors()[0].kind, ParseErrorKind::NestingTooDeep);
+        }
+
+        let input = format!("{}1{}", "(".repeat(50), ")".repeat(50));
+        let l = Lexer::new(&input);
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        assert_eq!(program.len(), 1);
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let input = "let add = func(a, b) { if (a < b) { return [a, b][0]; } else { a * -b } };
+        add(1, \"two\")[3];";
+        for end in 0..=input.len() {
+            let l = Lexer::new(&input[..end]);
+            let mut p = Parser::new(l);
+
+            p.parse_program();
+        }
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let tests = vec![
+            ("{}", 0),
+            ("{\"one\": 1, \"two\": 1 + 1, 3: [3]}", 3),
+            ("{true: 1, false: 0,}", 2),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            assert_eq!(program.len(), 1, "{:?}", program);
+            if let Statement::Expression {
+                expression: Expression::Literal(Literal::Hash(pairs), _),
+                ..
+            } = &program[0]
+            {
+                assert_eq!(pairs.len(), expected, "{:?}", pairs);
+            } else {
+                panic!("Expected a Hash Literal but received.. {:?}", program[0])
+            }
+        }
+
+        let l = Lexer::new("{\"one\" 1}");
+        let mut p = Parser::new(l);
+
+        p.parse_program();
+        assert_eq!(p.get_errors()[0].expected, vec![TokenKind::COLON]);
+    }
+
+    fn parenthesize(expression: &Expression) -> String {
+        match expression {
+            Expression::Identifier(ident) => ident.literal.clone(),
+            Expression::Literal(Literal::Int { value, .. }, _) => value.to_string(),
+            Expression::Literal(Literal::String { value, .. }, _) => value.clone(),
+            Expression::Prefix(prefix, right, _) => format!("({}{})", prefix, parenthesize(right)),
+            Expression::Index(left, index, _) => {
+                format!("({}[{}])", parenthesize(left), parenthesize(index))
+            }
+            Expression::Infix(infix, left, right, _) => {
+                format!("({} {} {})", parenthesize(left), infix, parenthesize(right))
+            }
+            Expression::Interpolation(parts, _) => {
+                let parts = parts.iter().map(parenthesize).collect::<Vec<_>>();
+                format!("(concat {})", parts.join(" "))
+            }
+            expression => format!("{:?}", expression),
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let tests = vec![
+            ("a <= b == c >= d", "((a <= b) == (c >= d))"),
+            ("a || b && c", "(a || (b && c))"),
+            ("a && b == c", "(a && (b == c))"),
+            ("a + b % c", "(a + (b % c))"),
+            ("2 ** 3 ** 2", "(2 ** (3 ** 2))"),
+            ("-2 ** 2", "(-(2 ** 2))"),
+            ("2 * 3 ** 2", "(2 * (3 ** 2))"),
+            ("a | b ^ c & d", "(a | (b ^ (c & d)))"),
+            ("a & b == c", "((a & b) == c)"),
+            ("1 << 2 + 3", "(1 << (2 + 3))"),
+            ("a >> 1 < b << 1", "((a >> 1) < (b << 1))"),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            if let Statement::Expression { expression, .. } = &program[0] {
+                assert_eq!(parenthesize(expression), expected, "{}", input);
+            } else {
+                panic!(
+                    "Expected a Expression Statement but received.. {:?}",
+                    program[0]
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_float_literal() {
+        let l = Lexer::new("1.5e3;");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        if let Statement::Expression {
+            expression: Expression::Literal(Literal::Float { value, .. }, _),
+            ..
+        } = &program[0]
+        {
+            assert_eq!(*value, 1500.0);
+        } else {
+            panic!("Expected a Float Literal but received.. {:?}", program[0])
+        }
+    }
+
+    #[test]
+    fn test_loop_statements() {
+        let l = Lexer::new("while i < 3 { i; break; }\nfor x in [1, 2] { if (x) { continue } };");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        assert_eq!(program.len(), 2, "{:?}", program);
+        match &program[0] {
+            Statement::While { body, span, .. } => {
+                assert_eq!(body.len(), 2);
+                assert!(matches!(body[1], Statement::Break(_)));
+                assert_eq!((span.start, span.end), (0, 25));
+            }
+            statement => panic!("Expected a While Statement but received.. {:?}", statement),
+        }
+        match &program[1] {
+            Statement::For {
+                variable, iterable, ..
+            } => {
+                assert_eq!(variable.literal, "x");
+                assert!(matches!(
+                    iterable,
+                    Expression::Literal(Literal::Array(_), _)
+                ));
+            }
+            statement => panic!("Expected a For Statement but received.. {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn test_loop_control_outside_loop() {
+        let inputs = [
+            "break;",
+            "if (true) { continue; }",
+            "while true { let f = func() { break; }; }",
+        ];
+        for input in inputs {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            p.parse_program();
+            let errors = p.get_errors();
+            assert_eq!(errors.len(), 1, "{}: {:?}", input, errors);
+            assert_eq!(errors[0].kind, ParseErrorKind::OutsideLoop, "{}", input);
+        }
+        assert_eq!(
+            {
+                let mut p = Parser::new(Lexer::new("continue"));
+                p.parse_program();
+                p.get_errors()[0].message()
+            },
+            "`continue` outside of a loop"
+        );
+    }
+
+    #[test]
+    fn test_assignment() {
+        let tests = vec![
+            ("x = 5;", "x", None),
+            ("x += 1", "x", Some(Infix::Plus)),
+            ("items[0] -= 2", "(items[0])", Some(Infix::Minus)),
+            ("grid[1][2] *= 3", "((grid[1])[2])", Some(Infix::Multiply)),
+            ("h[\"k\"] /= 4", "(h[k])", Some(Infix::Divide)),
+        ];
+        for (input, expected_target, expected_operator) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            assert_eq!(program.len(), 1, "{}", input);
+            match &program[0] {
+                Statement::Assign {
+                    target, operator, ..
+                } => {
+                    assert_eq!(parenthesize(target), expected_target, "{}", input);
+                    assert_eq!(*operator, expected_operator, "{}", input);
+                }
+                statement => panic!(
+                    "Expected an Assign Statement but received.. {:?}",
+                    statement
+                ),
+            }
+        }
+
+        let l = Lexer::new("f() = 1; 1 + x += 2; let y = 3;");
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        let errors = p.get_errors();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!(errors[0].kind, ParseErrorKind::InvalidAssignmentTarget);
+        assert_eq!(
+            errors[0].message(),
+            "cannot assign to this expression with `=`"
+        );
+        assert_eq!((errors[1].span.start, errors[1].span.end), (9, 14));
+        assert_eq!(program.len(), 1, "{:?}", program);
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let input = "/// Adds one.
+/// Works on floats too.
+let inc = func(x) { x + 1 };
+let plain = 1;
+/// Not attached to anything.
+plain;
+let h = {
+    /// Ignored inside expressions.
+    \"a\": 1,
+};";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(l);
+
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        let docs = program
+            .iter()
+            .map(|statement| match statement {
+                Statement::Let { doc, .. } => doc.clone(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            docs,
+            vec![
+                Some("Adds one.\nWorks on floats too.".to_string()),
+                None,
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let tests = [
+            (
+                "\"total: ${count * 2} items\"",
+                "(concat total:  (count * 2)  items)",
+            ),
+            ("\"${a}${b[0]}\"", "(concat a (b[0]))"),
+            ("\"${\"x${y}\"}!\"", "(concat (concat x y) !)"),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            match &program[0] {
+                Statement::Expression { expression, .. } => {
+                    assert_eq!(parenthesize(expression), expected, "{}", input)
+                }
+                statement => panic!("Expected an expression statement, got {:?}", statement),
+            }
+        }
+
+        for input in ["\"${}\"", "\"${a b}\"", "\"${a"] {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+            p.parse_program();
+            assert!(!p.get_errors().is_empty(), "Expected errors for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let tests = [
+            ("0xFF", 255),
+            ("0o17", 15),
+            ("0b1010", 10),
+            ("1_000_000", 1_000_000),
+            ("0x_dead_BEEF", 0xdead_beef),
+            ("9223372036854775807", i64::MAX),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            match &program[0] {
+                Statement::Expression {
+                    expression: Expression::Literal(Literal::Int { value, .. }, _),
+                    ..
+                } => assert_eq!(*value, expected, "{}", input),
+                statement => panic!("Expected an integer literal, got {:?}", statement),
+            }
+        }
+
+        let l = Lexer::new("1_000.5e1_0");
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        check_parser_errors(&mut p);
+        assert!(matches!(
+            &program[0],
+            Statement::Expression {
+                expression: Expression::Literal(Literal::Float { value, .. }, _),
+                ..
+            } if *value == 1000.5e10
+        ));
+
+        let tests = [
+            ("9223372036854775808", "9223372036854775808"),
+            ("99_999_999_999_999_999_999", "99999999999999999999"),
+            ("0x1_0000_0000_0000_0000", "18446744073709551616"),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+            let program = p.parse_program();
+            check_parser_errors(&mut p);
+            match &program[0] {
+                Statement::Expression {
+                    expression: Expression::Literal(Literal::BigInt { value, .. }, _),
+                    ..
+                } => assert_eq!(value.to_string(), expected, "{}", input),
+                statement => panic!("Expected a big integer literal, got {:?}", statement),
+            }
+        }
+
+        let tests = [
+            (
+                "0b102",
+                ParseErrorKind::InvalidInteger,
+                "`2` is not a binary digit",
+            ),
+            (
+                "0x",
+                ParseErrorKind::InvalidInteger,
+                "expected hexadecimal digits",
+            ),
+        ];
+        for (input, kind, help) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(l);
+
+            p.parse_program();
+            let error = &p.get_errors()[0];
+            assert_eq!(error.kind, kind, "{}", input);
+            assert_eq!(error.help.as_deref(), Some(help), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let tests = [
+            ("a + b * c - d / e", "a + b * c - d / e;"),
+            ("(a + b) * (c - d)", "(a + b) * (c - d);"),
+            ("a - (b + c)", "a - (b + c);"),
+            ("-(a + b) ** 2", "-(a + b) ** 2;"),
+            ("(-a) ** -b ** c", "(-a) ** -b ** c;"),
+            ("!(a == b) || c && d", "!(a == b) || c && d;"),
+            ("1 << 2 + 3 & 4", "1 << 2 + 3 & 4;"),
+            ("(a | b) ^ c", "(a | b) ^ c;"),
+            ("add(1, (2 + 3))[0]", "add(1, 2 + 3)[0];"),
+            ("x[1] += 2", "x[1] += 2;"),
+            (
+                "/// Adds.\nlet add = func(a, b) { return a + b; }",
+                "/// Adds.\nlet add = func(a, b) {\n    return a + b;\n};",
+            ),
+            (
+                "if (a) { b } else { if (c) { d } }",
+                "if a { b } else { if c { d } }",
+            ),
+            ("\"${a}-${\"b\"}\"", "\"${a}-b\";"),
+        ];
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            let source = program[0].to_string();
+            assert_eq!(source, expected, "input: {}", input);
+
+            let mut p = Parser::new(Lexer::new(&source));
+            let reparsed = p.parse_program();
+            check_parser_errors(&mut p);
+            assert_eq!(reparsed[0].to_string(), source, "input: {}", input);
+        }
+    }
 }
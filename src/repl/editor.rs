@@ -0,0 +1,568 @@
+//! A small line editor for the REPL: cursor movement, word jumps, history
+//! navigation and reverse search, drawn with ANSI escape codes on a terminal
+//! in raw mode. When stdin isn't a terminal, lines are read as they are.
+
+use std::io::{self, BufRead, Read, Write};
+
+use super::{
+    history::History,
+    terminal::{self, RawMode},
+};
+
+/// What a call to [`LineEditor::read_line`] produced.
+pub enum Input {
+    Line(String),
+    /// Ctrl-C abandoned the line.
+    Interrupted,
+    /// Stdin was closed, or Ctrl-D was pressed on an empty line.
+    Eof,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    Up,
+    Down,
+    KillToEnd,
+    KillToStart,
+    KillWordBack,
+    ClearScreen,
+    ReverseSearch,
+    Cancel,
+    Interrupt,
+    EndOfInput,
+    Unknown,
+}
+
+/// Decodes the next key press from the bytes a raw-mode terminal sends.
+/// Returns `None` when the input ends.
+pub fn decode_key(bytes: &mut impl Iterator<Item = u8>) -> Option<Key> {
+    let key = match bytes.next()? {
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::EndOfInput,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x07 => Key::Cancel,
+        0x08 | 0x7f => Key::Backspace,
+        b'\t' => Key::Tab,
+        0x0b => Key::KillToEnd,
+        0x0c => Key::ClearScreen,
+        b'\r' | b'\n' => Key::Enter,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x12 => Key::ReverseSearch,
+        0x15 => Key::KillToStart,
+        0x17 => Key::KillWordBack,
+        0x1b => decode_escape(bytes),
+        byte if byte < 0x20 => Key::Unknown,
+        byte => {
+            // Gather the rest of a multi-byte UTF-8 character.
+            let len = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            let mut buf = vec![byte];
+            buf.extend(bytes.take(len - 1));
+            match std::str::from_utf8(&buf)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(ch) => Key::Char(ch),
+                None => Key::Unknown,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// Decodes what follows an escape byte: `ESC [ ...` and `ESC O ...` sequences
+/// for the arrow, home, end and delete keys, or Alt combined with a key.
+fn decode_escape(bytes: &mut impl Iterator<Item = u8>) -> Key {
+    match bytes.next() {
+        Some(b'[') => {
+            let mut params = String::new();
+            let last = loop {
+                match bytes.next() {
+                    Some(byte @ 0x40..=0x7e) => break byte,
+                    Some(byte) => params.push(byte as char),
+                    None => return Key::Unknown,
+                }
+            };
+            // `1;5C` is Ctrl+Right and `1;3C` is Alt+Right.
+            let modified = params.ends_with(";5") || params.ends_with(";3");
+            match (last, params.as_str()) {
+                (b'A', _) => Key::Up,
+                (b'B', _) => Key::Down,
+                (b'C', _) if modified => Key::WordRight,
+                (b'D', _) if modified => Key::WordLeft,
+                (b'C', _) => Key::Right,
+                (b'D', _) => Key::Left,
+                (b'H', _) | (b'~', "1" | "7") => Key::Home,
+                (b'F', _) | (b'~', "4" | "8") => Key::End,
+                (b'~', "3") => Key::Delete,
+                _ => Key::Unknown,
+            }
+        }
+        Some(b'O') => match bytes.next() {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            _ => Key::Unknown,
+        },
+        Some(b'b') => Key::WordLeft,
+        Some(b'f') => Key::WordRight,
+        Some(0x7f) => Key::KillWordBack,
+        Some(0x1b) => Key::Cancel,
+        _ => Key::Unknown,
+    }
+}
+
+/// The text being edited and the cursor position within it, in characters.
+#[derive(Default)]
+pub struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl LineBuffer {
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Replaces the text, leaving the cursor at its end.
+    pub fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    /// Applies an editing or cursor movement key. Returns `false` for keys
+    /// that don't edit the line.
+    pub fn apply(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(ch) => {
+                self.chars.insert(self.cursor, ch);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            Key::WordLeft => self.cursor = self.word_start(),
+            Key::WordRight => self.cursor = self.word_end(),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.chars.len(),
+            Key::KillToEnd => self.chars.truncate(self.cursor),
+            Key::KillToStart => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillWordBack => {
+                let start = self.word_start();
+                self.chars.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::Backspace | Key::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// The identifier that ends at the cursor, or a meta-command like `:his`
+    /// when it is the only thing on the line.
+    pub fn word_before_cursor(&self) -> String {
+        let mut start = self.cursor;
+        while start > 0 && is_word_char(self.chars[start - 1]) {
+            start -= 1;
+        }
+        if start > 0
+            && self.chars[start - 1] == ':'
+            && self.chars[..start - 1].iter().all(|ch| ch.is_whitespace())
+        {
+            start -= 1;
+        }
+        self.chars[start..self.cursor].iter().collect()
+    }
+
+    /// Completes the word before the cursor as far as all `candidates` agree.
+    /// Each candidate must start with that word. Returns `false` if nothing
+    /// could be added.
+    pub fn complete(&mut self, candidates: &[String]) -> bool {
+        let word = self.word_before_cursor().chars().count();
+        let Some((first, rest)) = candidates.split_first() else {
+            return false;
+        };
+        let common = rest.iter().fold(first.chars().count(), |len, candidate| {
+            first
+                .chars()
+                .zip(candidate.chars())
+                .take(len)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        for ch in first.chars().take(common).skip(word) {
+            self.apply(Key::Char(ch));
+        }
+        common > word
+    }
+
+    /// Where the word before the cursor starts, skipping any gap before it.
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && !is_word_char(self.chars[i - 1]) {
+            i -= 1;
+        }
+        while i > 0 && is_word_char(self.chars[i - 1]) {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Where the word after the cursor ends, skipping any gap before it.
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.chars.len() && !is_word_char(self.chars[i]) {
+            i += 1;
+        }
+        while i < self.chars.len() && is_word_char(self.chars[i]) {
+            i += 1;
+        }
+        i
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch == '_' || ch.is_alphanumeric()
+}
+
+/// How a Ctrl-R search ended.
+enum Search {
+    /// Keep editing the matched line.
+    Accept(String),
+    /// Submit the matched line straight away.
+    Submit(String),
+    Cancel,
+}
+
+/// Suggests the words that could complete the given prefix.
+pub type Completer = Box<dyn Fn(&str) -> Vec<String>>;
+
+pub struct LineEditor {
+    history: History,
+    interactive: bool,
+    completer: Option<Completer>,
+}
+
+impl LineEditor {
+    /// An editor for stdin. Its history is persisted only when a person is
+    /// typing at a terminal, so piped input doesn't end up in the file.
+    pub fn new() -> Self {
+        let interactive = terminal::is_interactive();
+        let history = if interactive {
+            History::load_default()
+        } else {
+            History::default()
+        };
+        Self {
+            history,
+            interactive,
+            completer: None,
+        }
+    }
+
+    /// Sets what the Tab key completes from.
+    pub fn set_completer(&mut self, completer: Completer) {
+        self.completer = Some(completer);
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Reads one line after showing `prompt` and records it in the history.
+    /// The line is returned without its line break.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Input> {
+        let raw_mode = if self.interactive {
+            RawMode::enable().ok()
+        } else {
+            None
+        };
+        let input = match raw_mode {
+            Some(_) => self.edit_line(prompt)?,
+            None => read_plain_line(prompt)?,
+        };
+        drop(raw_mode);
+
+        if let Input::Line(ref line) = input {
+            // Losing the history file shouldn't interrupt the session.
+            if let Err(error) = self.history.push(line) {
+                eprintln!("warning: couldn't save the REPL history: {}", error);
+            }
+        }
+        Ok(input)
+    }
+
+    fn edit_line(&mut self, prompt: &str) -> io::Result<Input> {
+        let mut keys = io::stdin().lock().bytes().map_while(Result::ok);
+        let mut line = LineBuffer::default();
+        // Position in the history while browsing it; `len` is the new line,
+        // whose text is kept in `draft`.
+        let mut index = self.history.len();
+        let mut draft = String::new();
+
+        refresh(prompt, &line)?;
+        loop {
+            let Some(key) = decode_key(&mut keys) else {
+                return Ok(Input::Eof);
+            };
+            match key {
+                Key::Enter => break,
+                Key::Interrupt => {
+                    println!("^C");
+                    return Ok(Input::Interrupted);
+                }
+                Key::EndOfInput if line.is_empty() => {
+                    println!();
+                    return Ok(Input::Eof);
+                }
+                Key::EndOfInput => {
+                    line.apply(Key::Delete);
+                }
+                Key::Up if index > 0 => {
+                    if index == self.history.len() {
+                        draft = line.text();
+                    }
+                    index -= 1;
+                    line.set(&self.history.entries()[index]);
+                }
+                Key::Down if index < self.history.len() => {
+                    index += 1;
+                    match self.history.entries().get(index) {
+                        Some(entry) => line.set(entry),
+                        None => line.set(&draft),
+                    }
+                }
+                Key::ReverseSearch => match self.reverse_search(&mut keys)? {
+                    Search::Accept(text) => line.set(&text),
+                    Search::Submit(text) => {
+                        line.set(&text);
+                        break;
+                    }
+                    Search::Cancel => {}
+                },
+                Key::ClearScreen => print!("\x1B[2J\x1B[H"),
+                Key::Tab => self.complete(&mut line),
+                key => {
+                    line.apply(key);
+                }
+            }
+            refresh(prompt, &line)?;
+        }
+
+        line.apply(Key::End);
+        refresh(prompt, &line)?;
+        println!();
+        Ok(Input::Line(line.text()))
+    }
+
+    /// Completes the word before the cursor. When that is ambiguous and can't
+    /// be extended any further, the candidates are listed below the line.
+    fn complete(&self, line: &mut LineBuffer) {
+        let word = line.word_before_cursor();
+        let mut candidates = match self.completer {
+            Some(ref completer) => completer(&word),
+            None => vec![],
+        };
+        candidates.retain(|candidate| candidate.starts_with(&word));
+        candidates.sort();
+        candidates.dedup();
+
+        if candidates.is_empty() {
+            print!("\x07");
+        } else if !line.complete(&candidates) && candidates.len() > 1 {
+            println!();
+            println!("{}", candidates.join("  "));
+        }
+    }
+
+    /// Runs a Ctrl-R search, showing the newest history entry that contains
+    /// what has been typed so far. Pressing Ctrl-R again moves to older matches.
+    fn reverse_search(&mut self, keys: &mut impl Iterator<Item = u8>) -> io::Result<Search> {
+        let mut query = String::new();
+        let mut found: Option<usize> = None;
+        loop {
+            let entry = found.map_or("", |i| self.history.entries()[i].as_str());
+            let label = if found.is_none() && !query.is_empty() {
+                "failed reverse-i-search"
+            } else {
+                "reverse-i-search"
+            };
+            print!("\r({})`{}': {}\x1B[K", label, query, entry);
+            io::stdout().flush()?;
+
+            let end = self.history.len();
+            match decode_key(keys) {
+                Some(Key::Char(ch)) => {
+                    query.push(ch);
+                    found = self
+                        .history
+                        .search_back(&query, found.map_or(end, |i| i + 1));
+                }
+                Some(Key::Backspace) => {
+                    query.pop();
+                    found = self.history.search_back(&query, end);
+                }
+                Some(Key::ReverseSearch) => {
+                    found = self
+                        .history
+                        .search_back(&query, found.unwrap_or(end))
+                        .or(found);
+                }
+                Some(Key::Enter) => return Ok(Search::Submit(entry.to_string())),
+                Some(Key::Cancel | Key::Interrupt) | None => return Ok(Search::Cancel),
+                Some(_) => return Ok(Search::Accept(entry.to_string())),
+            }
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Redraws the prompt and line, then puts the cursor back where it belongs.
+fn refresh(prompt: &str, line: &LineBuffer) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    write!(out, "\r{}{}\x1B[K\r", prompt, line.text())?;
+    let column = prompt.chars().count() + line.cursor;
+    if column > 0 {
+        write!(out, "\x1B[{}C", column)?;
+    }
+    out.flush()
+}
+
+fn read_plain_line(prompt: &str) -> io::Result<Input> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut buffer = String::new();
+    if io::stdin().lock().read_line(&mut buffer)? == 0 {
+        return Ok(Input::Eof);
+    }
+    let line = buffer.strip_suffix('\n').unwrap_or(&buffer);
+    Ok(Input::Line(
+        line.strip_suffix('\r').unwrap_or(line).to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod editor_test {
+    use super::*;
+
+    #[test]
+    fn test_decode_key() {
+        let input = "a\x1B[D\x1B[1;5C\x1BOH\x1B[3~\x1Bb\x7f\x12\r".bytes();
+        let input = input.chain("é日".bytes());
+        let mut bytes = input;
+        let expected = [
+            Key::Char('a'),
+            Key::Left,
+            Key::WordRight,
+            Key::Home,
+            Key::Delete,
+            Key::WordLeft,
+            Key::Backspace,
+            Key::ReverseSearch,
+            Key::Enter,
+            Key::Char('é'),
+            Key::Char('日'),
+        ];
+        for (i, key) in expected.iter().enumerate() {
+            assert_eq!(decode_key(&mut bytes), Some(*key), "keys[{}]", i);
+        }
+        assert_eq!(decode_key(&mut bytes), None);
+    }
+
+    #[test]
+    fn test_line_buffer() {
+        let mut line = LineBuffer::default();
+        line.set("let total = add(x, y)");
+
+        line.apply(Key::WordLeft);
+        line.apply(Key::WordLeft);
+        assert_eq!(line.cursor, 16);
+        line.apply(Key::KillWordBack);
+        assert_eq!(line.text(), "let total = x, y)");
+
+        line.apply(Key::Home);
+        line.apply(Key::WordRight);
+        line.apply(Key::Char('!'));
+        assert_eq!(line.text(), "let! total = x, y)");
+
+        line.apply(Key::KillToEnd);
+        line.apply(Key::Backspace);
+        line.apply(Key::Home);
+        line.apply(Key::Delete);
+        assert_eq!(line.text(), "et");
+
+        line.apply(Key::End);
+        line.apply(Key::KillToStart);
+        assert!(line.is_empty());
+        assert!(!line.apply(Key::Up));
+    }
+
+    #[test]
+    fn test_completion() {
+        let candidates = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        let mut line = LineBuffer::default();
+
+        line.set("1 + pu");
+        assert_eq!(line.word_before_cursor(), "pu");
+        assert!(line.complete(&candidates(&["putln"])));
+        assert_eq!(line.text(), "1 + putln");
+
+        line.set("l");
+        assert!(line.complete(&candidates(&["len", "let"])));
+        assert_eq!(line.text(), "le");
+        assert!(!line.complete(&candidates(&["len", "let"])));
+
+        line.set("  :hi");
+        assert_eq!(line.word_before_cursor(), ":hi");
+        line.set("{\"a\":hi");
+        assert_eq!(line.word_before_cursor(), "hi");
+
+        line.set("pu(1)");
+        line.apply(Key::Home);
+        line.apply(Key::Right);
+        line.apply(Key::Right);
+        assert!(line.complete(&candidates(&["putln"])));
+        assert_eq!(line.text(), "putln(1)");
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+/// Most entries kept in memory and loaded from the history file.
+const MAX_ENTRIES: usize = 1000;
+
+/// Lines entered at the REPL, oldest first, optionally backed by a file so
+/// they survive between sessions.
+#[derive(Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// History stored in `~/.racoon_history`, if there is a home directory.
+    pub fn load_default() -> Self {
+        match std::env::var_os("HOME") {
+            Some(home) => Self::load(PathBuf::from(home).join(".racoon_history")),
+            None => Self::default(),
+        }
+    }
+
+    /// Reads the history in `path`, which is created on the first `push` if it
+    /// doesn't exist yet. A file holding more than `MAX_ENTRIES` is rewritten
+    /// with just the newest ones, so it doesn't keep growing.
+    pub fn load(path: PathBuf) -> Self {
+        let mut entries = match File::open(&path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .map(|line| unescape(&line))
+                .collect(),
+            Err(_) => vec![],
+        };
+        let trimmed = entries.len() > MAX_ENTRIES;
+        if trimmed {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        let history = Self {
+            entries,
+            path: Some(path),
+        };
+        if trimmed {
+            // History is a convenience, so failing to trim it isn't reported.
+            let _ = history.rewrite();
+        }
+        history
+    }
+
+    /// Replaces the history file with the entries in memory.
+    fn rewrite(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file = File::create(path)?;
+        for entry in &self.entries {
+            writeln!(file, "{}", escape(entry))?;
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Records `line` and appends it to the history file. Blank lines and
+    /// repeats of the previous entry are skipped.
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return Ok(());
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", escape(line))?;
+        }
+        Ok(())
+    }
+
+    /// Finds the newest entry before `before` that contains `query`.
+    pub fn search_back(&self, query: &str, before: usize) -> Option<usize> {
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
+
+/// Entries are stored one per line, so line breaks and backslashes are escaped.
+fn escape(line: &str) -> String {
+    line.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            (ch, _) => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod history_test {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let path = std::env::temp_dir().join(format!("racoon_history_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut history = History::load(path.clone());
+        for line in ["let x = 1;", "let x = 1;", "  ", "x + 1", "\"a\\nb\"\nc"] {
+            history.push(line).unwrap();
+        }
+        assert_eq!(history.entries(), ["let x = 1;", "x + 1", "\"a\\nb\"\nc"]);
+        assert_eq!(history.search_back("x", 3), Some(1));
+        assert_eq!(history.search_back("x", 1), Some(0));
+        assert_eq!(history.search_back("y", 3), None);
+
+        let reloaded = History::load(path.clone());
+        assert_eq!(reloaded.entries(), history.entries());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_history_trimmed() {
+        let path = std::env::temp_dir().join(format!("racoon_trim_{}", std::process::id()));
+        let lines = (0..MAX_ENTRIES + 5)
+            .map(|i| format!("{}\n", i))
+            .collect::<String>();
+        std::fs::write(&path, lines).unwrap();
+
+        let history = History::load(path.clone());
+        assert_eq!(history.len(), MAX_ENTRIES);
+        assert_eq!(history.entries()[0], "5");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), MAX_ENTRIES);
+        assert_eq!(contents.lines().next(), Some("5"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,30 +1,93 @@
-use std::{cell::RefCell, cmp::min, io::Write, rc::Rc};
+mod editor;
+mod history;
+mod printer;
+mod terminal;
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use editor::{Input, LineEditor};
 
 use crate::{
-    evaluator::{self, builltin_funcs::new_builtins, Environment, Evaluator, Object},
-    lexer::Lexer,
+    abstract_tree::{Expression, Program, Statement},
+    diagnostic, dump,
+    evaluator::{builltin_funcs::new_builtins, Environment, Evaluator, Object},
+    lexer::{Lexer, KEYWORDS},
+    optimiser,
     parser::Parser,
     tkn::TokenKind,
 };
 
+/// Commands the REPL handles itself instead of evaluating, with their help text.
+const META_COMMANDS: [(&str, &str); 14] = [
+    (":help", "show this list"),
+    (":quit", "leave the REPL"),
+    (":clear", "clear the screen"),
+    (":history", "show the lines entered so far"),
+    (":paste", "enter a block of code, finished by `:end`"),
+    (":tokens", "<code>  show the tokens `code` lexes into"),
+    (
+        ":ast",
+        "<code>  show the syntax tree `code` parses into as an S-expression",
+    ),
+    (
+        ":env",
+        "list the bindings made in this session and their types",
+    ),
+    (":type", "<expr>  show the type of `expr`"),
+    (":time", "<expr>  evaluate `expr` and show how long it took"),
+    (":load", "<file>  run a script in this session"),
+    (
+        ":save",
+        "<file>  write the inputs that ran successfully to a file",
+    ),
+    (":reset", "forget every binding made in this session"),
+    (":end", "finish `:paste` mode"),
+];
+
+const PROMPT: &str = ">>> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct REPL {
-    pub command_buffer: Vec<String>,
+    editor: LineEditor,
+    /// The session's global scope.
+    env: Rc<RefCell<Environment>>,
+    evaluator: Evaluator,
+    /// Inputs that evaluated without errors, for `:save`.
+    session: Vec<String>,
+    /// Whether inputs are optimised before they run; off for `-O0`.
+    optimise: bool,
 }
 
 impl Default for REPL {
     fn default() -> Self {
-        Self::new()
+        Self::new(true)
     }
 }
 
 #[allow(clippy::print_literal)]
 impl REPL {
-    pub fn new() -> Self {
+    pub fn new(optimise: bool) -> Self {
+        let env = Rc::new(RefCell::new(Environment::from(new_builtins())));
+        let mut editor = LineEditor::new();
+        let completion_env = Rc::clone(&env);
+        editor.set_completer(Box::new(move |word| {
+            completions(&completion_env.borrow(), word)
+        }));
         Self {
-            command_buffer: vec![],
+            editor,
+            evaluator: Evaluator::new(Rc::clone(&env)),
+            env,
+            session: vec![],
+            optimise,
         }
     }
 
+    #[allow(clippy::ptr_arg)]
     pub fn print_errors(&mut self, errors: &Vec<String>) {
         let ascii_art = "   
     /\\_/\\  
@@ -32,97 +95,472 @@ impl REPL {
    =\\  /=";
         println!("\t {} \n Racoon fell into some errors. \n ", ascii_art);
         for msg in errors.iter() {
-            eprintln!("\t{}\n", msg);
+            for line in msg.lines() {
+                eprintln!("\t{}", line);
+            }
+            eprintln!();
         }
     }
 
     pub fn run(&mut self) {
         println!("{}", "b\x1B[2J\x1B[1;1H");
         println!("Welcome to Racoon v{}!! [Rust] ", env!("CARGO_PKG_VERSION"),);
-        let mut buffer = String::new();
-        let mut env = Environment::from(new_builtins());
-
-        env.set(
-            "putln".to_string(),
-            &Object::BUILTIN {
-                arity: -1,
-                builtInFunc: |args| {
-                    for arg in args.iter() {
-                        println!("{}", arg);
-                    }
-                    Object::NIL
-                },
-            },
-        );
-        let mut evaluator = Evaluator::new(Rc::new(RefCell::new(env)));
-
-        loop {
-            buffer.clear();
-            let stdin = std::io::stdin();
-
-            print!(">>> ");
 
-            if let Err(e) = std::io::stdout().flush() {
-                self.print_errors(&vec![format!(
-                    "InputBufferError: Unable to flush stdout \n Error Details: {}",
-                    e
-                )]);
+        while let Some(input) = self.read_input() {
+            let buffer = input.trim();
+            if buffer.is_empty() {
+                continue;
             }
-
-            if let Err(e) = stdin.read_line(&mut buffer) {
-                self.print_errors(&vec![format!(
-                    "InputBufferError: Unable to take input from the user. \n Error Details: {}",
-                    e
-                )]);
+            if !buffer.starts_with(':') {
+                self.execute(buffer);
+                continue;
             }
 
-            let buffer = buffer.trim();
-            self.command_buffer.push(String::from(buffer));
-            match buffer {
-                ":quit" => {
-                    eprintln!("See you again!!");
-                    std::process::exit(0);
+            let (command, argument) = match buffer.split_once(char::is_whitespace) {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (buffer, ""),
+            };
+            match command {
+                ":quit" => break,
+                ":help" => {
+                    for (command, help) in META_COMMANDS {
+                        println!("  {:<10} {}", command, help);
+                    }
                 }
                 ":clear" => {
                     println!("{}", "b\x1B[2J\x1B[1;1H");
                 }
                 ":history" => {
                     println!("-- Command History --");
-                    let n = self.command_buffer.len();
-                    for i in 0..min(n, 10_usize) {
-                        println!(" -> {}", self.command_buffer[n - i - 1])
+                    for (i, entry) in self.editor.history().entries().iter().enumerate() {
+                        println!(" {:>4}  {}", i + 1, entry)
                     }
                     println!("-- END --")
                 }
-
-                _ => {
-                    let l = Lexer::new(buffer);
-
-                    // let mut tok = l.next_token();
-                    // while tok.kind != TokenKind::EOF {
-                    //     println!("{:?}", tok);
-                    //     tok = l.next_token();
-                    // }
-                    let mut parser = Parser::new(l);
-                    let program = parser.parse_program();
-                    if !parser.get_errors().is_empty() {
-                        self.print_errors(parser.get_errors());
-                        continue;
+                ":paste" => {
+                    println!("-- Paste mode: finish with `:end` on its own line --");
+                    let block = self.read_paste();
+                    self.execute(&block);
+                }
+                ":tokens" => {
+                    let mut lexer = Lexer::new(argument);
+                    loop {
+                        let tok = lexer.next_token();
+                        if tok.kind == TokenKind::EOF {
+                            break;
+                        }
+                        println!(
+                            "  {:<12} {:<16} {}",
+                            tok.kind,
+                            format!("{:?}", tok.literal),
+                            tok.span
+                        );
+                    }
+                }
+                ":ast" => {
+                    if let Some(program) = self.parse(argument) {
+                        println!("{}", dump::program_to_sexp(&program));
                     }
-                    // for statement in program.iter() {
-                    //     println!("{:?}", statement);
-                    // }
-                    match evaluator.evaluate(program) {
-                        Some(obj) => match obj {
-                            Object::ERROR(error_msg) => self.print_errors(&vec![error_msg]),
-                            _ => println!("{:?}", obj),
-                        },
-
-                        None => {}
+                }
+                ":env" => {
+                    let mut env = self.env.borrow_mut();
+                    for name in env.names() {
+                        match env.get(name.clone()) {
+                            // Builtins are always there, so they aren't listed.
+                            Some(Object::BUILTIN { .. }) | None => {}
+                            Some(value) => println!("  {}: {}", name, Object::type_of(value)),
+                        }
+                    }
+                }
+                ":type" => {
+                    if let Some(value) = self.evaluate(argument) {
+                        println!("{}", Object::type_of(value));
                     }
-                    // println!("DEBUGGING: {:?}", env);'
                 }
+                ":time" => {
+                    if let Some(elapsed) = self.execute(argument) {
+                        println!("-- took {:?} --", elapsed);
+                    }
+                }
+                ":load" => match std::fs::read_to_string(argument) {
+                    Ok(source) => {
+                        if self.evaluate(&source).is_some() {
+                            self.session.push(source);
+                        }
+                    }
+                    Err(e) => {
+                        self.print_errors(&vec![format!("Unable to read {}: {}", argument, e)])
+                    }
+                },
+                ":save" => match std::fs::write(argument, self.saved_session()) {
+                    Ok(()) => {
+                        println!("-- Saved {} inputs to {} --", self.session.len(), argument)
+                    }
+                    Err(e) => {
+                        self.print_errors(&vec![format!("Unable to write {}: {}", argument, e)])
+                    }
+                },
+                ":reset" => {
+                    self.reset();
+                    println!("-- The environment was reset --");
+                }
+                _ => self.print_errors(&vec![format!(
+                    "Unknown command `{}`. Type `:help` to list the commands.",
+                    command
+                )]),
+            }
+        }
+        eprintln!("See you again!!");
+    }
+
+    /// Runs one submission, printing its value and remembering it as `_`.
+    /// Returns how long the evaluation took, if it succeeded.
+    fn execute(&mut self, buffer: &str) -> Option<Duration> {
+        let program = self.parse(buffer)?;
+        let is_expression = matches!(program.as_slice(), [Statement::Expression { .. }]);
+        // A name on its own shows the doc comment it was declared with.
+        let doc = match program.as_slice() {
+            [Statement::Expression {
+                expression: Expression::Identifier(ident),
+                ..
+            }] => self.env.borrow().doc(&ident.literal),
+            _ => None,
+        };
+
+        let start = Instant::now();
+        let value = self.run_program(program);
+        let elapsed = start.elapsed();
+
+        let value = value?;
+        let source = buffer.trim_end().trim_end_matches(';');
+        // Saved sessions must set `_` themselves. Inputs with comments are kept
+        // as they are, since a comment could swallow the `;` that would follow.
+        if self.show(value, doc)
+            && is_expression
+            && !source.contains("//")
+            && !source.contains("/*")
+        {
+            self.session.push(format!("let _ = {};", source));
+        } else {
+            self.session.push(buffer.to_string());
+        }
+        Some(elapsed)
+    }
+
+    /// The inputs that ran successfully, as a script for `:save`.
+    fn saved_session(&self) -> String {
+        let mut contents = self.session.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    /// Forgets every binding and input of the session.
+    fn reset(&mut self) {
+        *self.env.borrow_mut() = Environment::from(new_builtins());
+        self.session.clear();
+    }
+
+    /// Prints a result, after `doc` or else the doc comment of a function, and
+    /// binds it to `_`. Nil isn't shown or remembered, in which case this
+    /// returns `false`.
+    fn show(&mut self, value: Object, doc: Option<String>) -> bool {
+        if value == Object::NIL {
+            return false;
+        }
+        let doc = doc.or_else(|| match &value {
+            Object::FUNCTION(_, _, _, doc) => doc.clone(),
+            _ => None,
+        });
+        if let Some(doc) = doc {
+            for line in doc.lines() {
+                println!("/// {}", line);
+            }
+        }
+        println!("{}", printer::pretty(&value));
+        self.env.borrow_mut().set("_".to_string(), &value);
+        true
+    }
+
+    /// Parses and evaluates `source` in the session, reporting any errors.
+    fn evaluate(&mut self, source: &str) -> Option<Object> {
+        let program = self.parse(source)?;
+        self.run_program(program)
+    }
+
+    fn run_program(&mut self, program: Program) -> Option<Object> {
+        let program = if self.optimise {
+            optimiser::optimise_input(program)
+        } else {
+            program
+        };
+        match self.evaluator.evaluate(program) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.print_errors(&vec![error.traceback()]);
+                None
+            }
+        }
+    }
+
+    fn parse(&mut self, source: &str) -> Option<Program> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        if !parser.get_errors().is_empty() {
+            let colour = diagnostic::use_colour();
+            let errors = parser
+                .get_errors()
+                .iter()
+                .map(|error| error.render(source, colour))
+                .collect::<Vec<_>>();
+            self.print_errors(&errors);
+            return None;
+        }
+        Some(program)
+    }
+
+    /// Reads one submission, prompting for more lines while it is incomplete.
+    /// Two blank lines in a row submit it regardless, and Ctrl-C discards it.
+    /// Returns `None` once stdin is closed and nothing was typed.
+    fn read_input(&mut self) -> Option<String> {
+        let mut input = match self.read_line(PROMPT)? {
+            Input::Line(line) => line,
+            Input::Interrupted => return Some(String::new()),
+            Input::Eof => return None,
+        };
+        let mut blank_lines = 0;
+        while is_incomplete(&input) && blank_lines < 2 {
+            let line = match self.read_line(CONTINUATION_PROMPT)? {
+                Input::Line(line) => line,
+                Input::Interrupted => return Some(String::new()),
+                Input::Eof => break,
+            };
+            if line.trim().is_empty() {
+                blank_lines += 1;
+            } else {
+                blank_lines = 0;
+            }
+            input.push('\n');
+            input.push_str(&line);
+        }
+        Some(input)
+    }
+
+    /// Collects lines verbatim until one reading `:end`, or until stdin closes.
+    fn read_paste(&mut self) -> String {
+        let mut block = String::new();
+        while let Some(Input::Line(line)) = self.read_line("") {
+            if line.trim() == ":end" {
+                break;
             }
+            block.push_str(&line);
+            block.push('\n');
         }
+        block
+    }
+
+    /// Reads a line with the line editor. Returns `None` if stdin can't be read.
+    fn read_line(&mut self, prompt: &str) -> Option<Input> {
+        match self.editor.read_line(prompt) {
+            Ok(input) => Some(input),
+            Err(e) => {
+                self.print_errors(&vec![format!(
+                    "InputBufferError: Unable to take input from the user. \n Error Details: {}",
+                    e
+                )]);
+                None
+            }
+        }
+    }
+}
+
+/// The meta-commands, or the bound names and keywords, that start with `word`.
+fn completions(env: &Environment, word: &str) -> Vec<String> {
+    let words = if word.starts_with(':') {
+        META_COMMANDS
+            .iter()
+            .map(|(command, _)| command.to_string())
+            .collect()
+    } else {
+        let mut words = env.names();
+        words.extend(KEYWORDS.iter().map(|(keyword, _)| keyword.to_string()));
+        words
+    };
+    words
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect()
+}
+
+/// Whether `source` looks like the start of a longer submission: it leaves a
+/// bracket, string or block comment open, or ends in the middle of an
+/// expression or statement.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut lexer = Lexer::new(source);
+    let mut depth = 0;
+    let mut last = TokenKind::EOF;
+    loop {
+        let tok = lexer.next_token();
+        match tok.kind {
+            TokenKind::EOF => break,
+            TokenKind::BLANK => continue,
+            // Unterminated strings and block comments.
+            TokenKind::ILLEGAL if tok.literal == "/*" || tok.literal.ends_with('"') => return true,
+            TokenKind::LPAREN | TokenKind::LBRACKET | TokenKind::LBRACE | TokenKind::STRINGHEAD => {
+                depth += 1
+            }
+            TokenKind::RPAREN | TokenKind::RBRACKET | TokenKind::RBRACE | TokenKind::STRINGTAIL => {
+                depth -= 1
+            }
+            _ => {}
+        }
+        last = tok.kind;
+    }
+
+    depth > 0
+        || matches!(
+            last,
+            TokenKind::ASSIGN
+                | TokenKind::PLUSEQ
+                | TokenKind::MINUSEQ
+                | TokenKind::ASTERISKEQ
+                | TokenKind::SLASHEQ
+                | TokenKind::PLUS
+                | TokenKind::MINUS
+                | TokenKind::BANG
+                | TokenKind::ASTERISK
+                | TokenKind::SLASH
+                | TokenKind::PERCENT
+                | TokenKind::POWER
+                | TokenKind::AND
+                | TokenKind::OR
+                | TokenKind::AMPERSAND
+                | TokenKind::PIPE
+                | TokenKind::CARET
+                | TokenKind::SHL
+                | TokenKind::SHR
+                | TokenKind::LT
+                | TokenKind::LTE
+                | TokenKind::GT
+                | TokenKind::GTE
+                | TokenKind::EQ
+                | TokenKind::NEQ
+                | TokenKind::COMMA
+                | TokenKind::COLON
+                | TokenKind::LET
+                | TokenKind::IF
+                | TokenKind::ELSE
+                | TokenKind::WHILE
+                | TokenKind::FOR
+                | TokenKind::IN
+                | TokenKind::FUNCTION
+                // A doc comment waits for the binding it documents.
+                | TokenKind::DOC
+        )
+}
+
+#[cfg(test)]
+mod repl_test {
+    use super::*;
+
+    #[test]
+    fn test_is_incomplete() {
+        let incomplete = [
+            "let add = func(a, b) {",
+            "[1, 2,",
+            "foo(1",
+            "\"unterminated",
+            "\"\"\"\nmany lines",
+            "r#\"raw",
+            "\"${x",
+            "/* comment",
+            "1 +",
+            "let x =",
+            "if (x) { 1 } else",
+            "/// Adds numbers.",
+        ];
+        for input in incomplete {
+            assert!(
+                is_incomplete(input),
+                "Expected {:?} to be incomplete",
+                input
+            );
+        }
+
+        let complete = [
+            "",
+            "let add = func(a, b) {\n  a + b\n};",
+            "[1, 2]",
+            "\"${x}\"",
+            "1 + 2 // trailing +",
+            "x)",
+            "let = ;",
+        ];
+        for input in complete {
+            assert!(!is_incomplete(input), "Expected {:?} to be complete", input);
+        }
+    }
+
+    #[test]
+    fn test_completions() {
+        let outer = Rc::new(RefCell::new(Environment::from(new_builtins())));
+        outer
+            .borrow_mut()
+            .set("counter".to_string(), &Object::INTEGER(1));
+        let mut env = Environment::new_with_outer(outer);
+        env.set("count".to_string(), &Object::INTEGER(2));
+
+        assert_eq!(completions(&env, "co"), ["count", "counter", "continue"]);
+        assert_eq!(completions(&env, "pu"), ["push", "putln"]);
+        assert_eq!(
+            completions(&env, "f"),
+            ["float", "floor", "func", "false", "for"]
+        );
+        assert_eq!(completions(&env, ":h"), [":help", ":history"]);
+        assert!(completions(&env, "zz").is_empty());
+    }
+    fn get(repl: &REPL, name: &str) -> Option<Object> {
+        repl.env.borrow_mut().get(name.to_string())
+    }
+
+    #[test]
+    fn test_last_value() {
+        let mut repl = REPL::new(true);
+        repl.execute("if (false) { 1 }").unwrap();
+        assert_eq!(get(&repl, "_"), None);
+
+        repl.execute("1 + 2").unwrap();
+        assert_eq!(get(&repl, "_"), Some(Object::INTEGER(3)));
+        repl.execute("if (false) { 1 }").unwrap();
+        assert_eq!(get(&repl, "_"), Some(Object::INTEGER(3)));
+        repl.execute("_ * 2").unwrap();
+        assert_eq!(get(&repl, "_"), Some(Object::INTEGER(6)));
+    }
+
+    #[test]
+    fn test_saved_session() {
+        let mut repl = REPL::new(true);
+        for input in ["let x = 1;", "x + 1;", "x // one", "putln(x)", "undefined"] {
+            repl.execute(input);
+        }
+        assert_eq!(
+            repl.saved_session(),
+            "let x = 1;\nlet _ = x + 1;\nx // one\nputln(x)\n"
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut repl = REPL::new(true);
+        repl.execute("let x = 1;").unwrap();
+        repl.execute("x").unwrap();
+        repl.reset();
+        assert_eq!(get(&repl, "x"), None);
+        assert_eq!(get(&repl, "_"), None);
+        assert!(matches!(get(&repl, "len"), Some(Object::BUILTIN { .. })));
+        assert_eq!(repl.saved_session(), "\n");
+
+        // The evaluator shares the new environment.
+        repl.execute("let y = 2;").unwrap();
+        assert_eq!(get(&repl, "y"), Some(Object::INTEGER(2)));
     }
 }
@@ -0,0 +1,201 @@
+//! Pretty printing of values for the REPL. Arrays and hashes that don't fit on
+//! one line are broken over indented lines, and very large values are cut short
+//! so that printing them can't flood the terminal.
+//!
+//! Values can't contain themselves: arrays and hashes are copied, and the only
+//! references are the environments captured by functions, which are never
+//! printed. Nesting is still limited so deep structures stay readable.
+
+use crate::{evaluator::Object, lexer::quote};
+
+/// Columns a nested value may take before it is broken over several lines.
+const WIDTH: usize = 80;
+/// Most elements shown from a single array or hash.
+const MAX_ITEMS: usize = 100;
+/// Deepest nesting shown; anything below is written as `[...]` or `{...}`.
+const MAX_DEPTH: usize = 16;
+/// Most characters shown from a single string.
+const MAX_STRING: usize = 1000;
+/// Most values shown in total.
+const MAX_VALUES: usize = 2000;
+
+/// Writes `value` as the REPL shows it.
+pub fn pretty(value: &Object) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        budget: MAX_VALUES,
+    };
+    printer.value(value, 0);
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    /// Values that may still be written before the rest are elided.
+    budget: usize,
+}
+
+impl Printer {
+    fn value(&mut self, value: &Object, depth: usize) {
+        self.budget = self.budget.saturating_sub(1);
+        match value {
+            Object::ARRAY(items) => {
+                let entries = items.iter().map(|item| (None, item)).collect();
+                self.collection(value, ("[", "]"), entries, depth);
+            }
+            Object::HASH(table) => {
+                let entries = table
+                    .iter()
+                    .map(|(key, value)| (Some(key.to_object()), value))
+                    .collect();
+                self.collection(value, ("{", "}"), entries, depth);
+            }
+            Object::STRING(s) if s.chars().count() > MAX_STRING => {
+                let shown = s.chars().take(MAX_STRING).collect::<String>();
+                let hidden = s.chars().count() - MAX_STRING;
+                self.out
+                    .push_str(&format!("{}... ({} more chars)", quote(&shown), hidden));
+            }
+            Object::RETURN(value) => self.value(value, depth),
+            value => self.out.push_str(&value.to_string()),
+        }
+    }
+
+    /// Writes an array or hash on the current line if it fits, and otherwise
+    /// with one entry per line, indented one level deeper than `depth`.
+    fn collection(
+        &mut self,
+        value: &Object,
+        (open, close): (&str, &str),
+        entries: Vec<(Option<Object>, &Object)>,
+        depth: usize,
+    ) {
+        if entries.is_empty() {
+            self.out.push_str(open);
+            self.out.push_str(close);
+            return;
+        }
+        if depth >= MAX_DEPTH {
+            self.out.push_str(&format!("{}...{}", open, close));
+            return;
+        }
+        if let Some(line) = flat(value, depth, WIDTH.saturating_sub(self.column())) {
+            self.out.push_str(&line);
+            return;
+        }
+
+        let indent = "  ".repeat(depth + 1);
+        self.out.push_str(open);
+        self.out.push('\n');
+        for (i, (key, item)) in entries.iter().enumerate() {
+            if i == MAX_ITEMS || self.budget == 0 {
+                self.out
+                    .push_str(&format!("{}... {} more\n", indent, entries.len() - i));
+                break;
+            }
+            self.out.push_str(&indent);
+            if let Some(key) = key {
+                self.out.push_str(&format!("{}: ", key));
+            }
+            self.value(item, depth + 1);
+            self.out.push_str(",\n");
+        }
+        self.out.push_str(&"  ".repeat(depth));
+        self.out.push_str(close);
+    }
+
+    fn column(&self) -> usize {
+        let line = match self.out.rfind('\n') {
+            Some(newline) => &self.out[newline + 1..],
+            None => &self.out,
+        };
+        line.chars().count()
+    }
+}
+
+/// Writes `value` on a single line, or gives up once it is longer than `limit`.
+fn flat(value: &Object, depth: usize, limit: usize) -> Option<String> {
+    let line = match value {
+        Object::ARRAY(items) if depth >= MAX_DEPTH && !items.is_empty() => String::from("[...]"),
+        Object::HASH(table) if depth >= MAX_DEPTH && !table.is_empty() => String::from("{...}"),
+        Object::ARRAY(items) => {
+            let mut line = String::from("[");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(", ");
+                }
+                line.push_str(&flat(item, depth + 1, limit.checked_sub(line.len())?)?);
+            }
+            line.push(']');
+            line
+        }
+        Object::HASH(table) => {
+            let mut line = String::from("{");
+            for (i, (key, value)) in table.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(", ");
+                }
+                line.push_str(&format!("{}: ", key.to_object()));
+                line.push_str(&flat(value, depth + 1, limit.checked_sub(line.len())?)?);
+            }
+            line.push('}');
+            line
+        }
+        Object::STRING(s) if s.len() > limit => return None,
+        value => value.to_string(),
+    };
+    (line.chars().count() <= limit).then_some(line)
+}
+
+#[cfg(test)]
+mod printer_test {
+    use super::*;
+    use crate::{
+        evaluator::{builltin_funcs::new_builtins, Environment, Evaluator},
+        lexer::Lexer,
+        parser::Parser,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    fn eval(input: &str) -> Object {
+        let env = Rc::new(RefCell::new(Environment::from(new_builtins())));
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        Evaluator::new(env).evaluate(program).unwrap()
+    }
+
+    #[test]
+    fn test_pretty() {
+        let tests = [
+            ("[1, \"two\", 3.0, true]", "[1, \"two\", 3.0, true]"),
+            ("{\"a\": [1, 2]}", "{\"a\": [1, 2]}"),
+            ("[[], {}]", "[[], {}]"),
+            ("func(a, b) { a + b }", "func(a, b) { ... }"),
+            ("len", "<builtin len>"),
+            (
+                "[\"aaaaaaaaaaaaaaaaaaaa\", \"bbbbbbbbbbbbbbbbbbbb\", [\"cccccccccccccccccccc\", \"dddddddddddddddddddd\"]]",
+                "[\n  \"aaaaaaaaaaaaaaaaaaaa\",\n  \"bbbbbbbbbbbbbbbbbbbb\",\n  [\"cccccccccccccccccccc\", \"dddddddddddddddddddd\"],\n]",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(pretty(&eval(input)), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_pretty_truncates() {
+        let long = pretty(&eval(
+            "let a = []; while len(a) < 150 { a = push(a, len(a)) }; a",
+        ));
+        assert!(long.starts_with("[\n  0,\n  1,\n"));
+        assert!(long.ends_with("  99,\n  ... 50 more\n]"), "{}", long);
+
+        let deep = pretty(&eval(
+            "let a = 1; let i = 0; while i < 20 { a = [a]; i += 1 }; a",
+        ));
+        assert!(deep.contains("[...]"), "{}", deep);
+
+        let string = pretty(&eval("let s = \"\"; while len(s) < 1100 { s += \"x\" }; s"));
+        assert!(string.ends_with("xxx\"... (100 more chars)"), "{}", string);
+    }
+}
@@ -0,0 +1,151 @@
+//! Switching the terminal in and out of raw mode, where key presses reach the
+//! REPL one at a time instead of a line at a time.
+
+use std::io::{self, IsTerminal};
+
+/// Whether both stdin and stdout are a terminal that the line editor can drive.
+/// Elsewhere the REPL reads plain lines.
+pub fn is_interactive() -> bool {
+    sys::SUPPORTED && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// The `termios` layout and flag values below are the ones Linux uses on these
+/// architectures; others, such as PowerPC, MIPS and SPARC, define them differently.
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+mod sys {
+    use std::io;
+
+    pub const SUPPORTED: bool = true;
+
+    const STDIN_FILENO: i32 = 0;
+    const TCSAFLUSH: i32 = 2;
+
+    // c_iflag
+    const ICRNL: u32 = 0o400;
+    const IXON: u32 = 0o2000;
+    // c_lflag
+    const ISIG: u32 = 0o1;
+    const ICANON: u32 = 0o2;
+    const ECHO: u32 = 0o10;
+    const IEXTEN: u32 = 0o100000;
+    // c_cc
+    const VTIME: usize = 5;
+    const VMIN: usize = 6;
+
+    /// `struct termios` as glibc and musl lay it out on Linux.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Termios {
+        c_iflag: u32,
+        c_oflag: u32,
+        c_cflag: u32,
+        c_lflag: u32,
+        c_line: u8,
+        c_cc: [u8; 32],
+        c_ispeed: u32,
+        c_ospeed: u32,
+    }
+
+    extern "C" {
+        fn tcgetattr(fd: i32, termios: *mut Termios) -> i32;
+        fn tcsetattr(fd: i32, optional_actions: i32, termios: *const Termios) -> i32;
+    }
+
+    pub fn get() -> io::Result<Termios> {
+        let mut termios = Termios {
+            c_iflag: 0,
+            c_oflag: 0,
+            c_cflag: 0,
+            c_lflag: 0,
+            c_line: 0,
+            c_cc: [0; 32],
+            c_ispeed: 0,
+            c_ospeed: 0,
+        };
+        // SAFETY: `termios` is a valid, writable `struct termios`.
+        if unsafe { tcgetattr(STDIN_FILENO, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(termios)
+    }
+
+    pub fn set(termios: &Termios) -> io::Result<()> {
+        // SAFETY: `termios` points to a valid `struct termios`.
+        if unsafe { tcsetattr(STDIN_FILENO, TCSAFLUSH, termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Turns off line buffering, echo, signal keys and flow control. Output
+    /// processing stays on so `\n` still starts a new line.
+    pub fn make_raw(mut termios: Termios) -> Termios {
+        termios.c_iflag &= !(ICRNL | IXON);
+        termios.c_lflag &= !(ISIG | ICANON | ECHO | IEXTEN);
+        termios.c_cc[VMIN] = 1;
+        termios.c_cc[VTIME] = 0;
+        termios
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+)))]
+mod sys {
+    use std::io;
+
+    pub const SUPPORTED: bool = false;
+
+    #[derive(Clone, Copy)]
+    pub struct Termios;
+
+    pub fn get() -> io::Result<Termios> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "raw mode is not supported on this platform",
+        ))
+    }
+
+    pub fn set(_: &Termios) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn make_raw(termios: Termios) -> Termios {
+        termios
+    }
+}
+
+/// Keeps the terminal in raw mode until dropped.
+pub struct RawMode {
+    original: sys::Termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        let original = sys::get()?;
+        sys::set(&sys::make_raw(original))?;
+        Ok(Self { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = sys::set(&self.original);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{cell::RefCell, process::ExitCode, rc::Rc};
+
+use crate::{
+    diagnostic,
+    evaluator::{builltin_funcs::new_builtins, Environment, Evaluator, Object},
+    lexer::Lexer,
+    optimiser,
+    parser::Parser,
+};
+
+/// Exit status of a program that failed to parse or raised an error.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit status for a bad command line or a script that can't be read.
+pub const EXIT_USAGE: u8 = 2;
+
+/// Runs the script at `path`. `args` are the command-line arguments that
+/// follow it, which the script sees as the `args` array. `optimise` is off
+/// for `-O0`.
+pub fn run_file(path: &str, args: &[String], optimise: bool) -> ExitCode {
+    match std::fs::read_to_string(path) {
+        Ok(source) => run_source(&source, args, optimise),
+        Err(error) => {
+            eprintln!("racoon: can't read {}: {}", path, error);
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+/// Lexes, parses and evaluates a whole program, reporting any error on stderr.
+pub fn run_source(source: &str, args: &[String], optimise: bool) -> ExitCode {
+    match run(source, args, optimise) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
+
+fn run(source: &str, args: &[String], optimise: bool) -> Result<(), String> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if !parser.get_errors().is_empty() {
+        let colour = diagnostic::use_colour();
+        let errors = parser
+            .get_errors()
+            .iter()
+            .map(|error| error.render(source, colour))
+            .collect::<Vec<_>>();
+        return Err(errors.join("\n\n"));
+    }
+    let program = if optimise {
+        optimiser::optimise(program)
+    } else {
+        program
+    };
+
+    let mut env = Environment::from(new_builtins());
+    let args = args.iter().map(|arg| Object::STRING(arg.clone())).collect();
+    env.set("args".to_string(), &Object::ARRAY(args));
+
+    let mut evaluator = Evaluator::new(Rc::new(RefCell::new(env)));
+    match evaluator.evaluate(program) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(error.traceback()),
+    }
+}
+
+#[cfg(test)]
+mod runner_test {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let args = ["a".to_string(), "b c".to_string()];
+        let script = "#!/usr/bin/env racoon
+let total = 0;
+for arg in args { total += len(arg); }
+if (total != 4) { undefined_name }";
+        assert_eq!(run(script, &args, true), Ok(()));
+
+        let error = run("let x = 1;\nx / 0", &[], true).unwrap_err();
+        assert!(
+            error.ends_with("ZeroDivisionError: Division by zero"),
+            "{}",
+            error
+        );
+
+        let error = run("let = 1", &[], true).unwrap_err();
+        assert!(
+            error.starts_with("error: expected an identifier"),
+            "{}",
+            error
+        );
+    }
+}
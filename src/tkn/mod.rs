@@ -1,10 +1,13 @@
+mod span;
 mod token_kind;
+pub use span::Span;
 pub use token_kind::TokenKind;
 
 #[derive(Debug, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub literal: String,
+    pub span: Span,
 }
 
 impl Default for Token {
@@ -12,6 +15,7 @@ impl Default for Token {
         Self {
             kind: TokenKind::EOF,
             literal: " ".to_string(),
+            span: Span::default(),
         }
     }
 }
@@ -21,12 +25,17 @@ impl Clone for Token {
         Self {
             kind: self.kind,
             literal: self.literal.clone(),
+            span: self.span,
         }
     }
 }
 
 impl Token {
     pub fn new(kind: TokenKind, literal: String) -> Self {
-        Self { kind, literal }
+        Self {
+            kind,
+            literal,
+            span: Span::default(),
+        }
     }
 }
@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+/// Location of a piece of source code.
+///
+/// `start` and `end` are byte offsets into the input (`end` is exclusive),
+/// while `line` and `column` are the 1-based position of `start`.
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns a span that starts where `self` starts and ends where `other` ends.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}:{}", self.line, self.column)
+    }
+}
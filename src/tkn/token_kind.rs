@@ -6,12 +6,26 @@ pub enum TokenKind {
     EOF,
     IDENT,
     INT,
+    FLOAT,
     ASSIGN,
+    PLUSEQ,
+    MINUSEQ,
+    ASTERISKEQ,
+    SLASHEQ,
     PLUS,
     MINUS,
     BANG,
     ASTERISK,
     SLASH,
+    PERCENT,
+    POWER,
+    AND,
+    OR,
+    AMPERSAND,
+    PIPE,
+    CARET,
+    SHL,
+    SHR,
     COMMA,
     SEMICOLON,
     COLON,
@@ -32,10 +46,22 @@ pub enum TokenKind {
     IF,
     ELSE,
     RETURN,
+    WHILE,
+    FOR,
+    IN,
+    BREAK,
+    CONTINUE,
     EQ,
     NEQ,
     BLANK,
     STRING,
+    /// The text of an interpolated string before its first `${`.
+    STRINGHEAD,
+    /// The text between two interpolations.
+    STRINGMID,
+    /// The text after the last interpolation.
+    STRINGTAIL,
+    DOC,
 }
 impl Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -44,12 +70,26 @@ impl Display for TokenKind {
             TokenKind::EOF => f.write_str("EOF"),
             TokenKind::IDENT => f.write_str("IDENT"),
             TokenKind::INT => f.write_str("INT"),
+            TokenKind::FLOAT => f.write_str("FLOAT"),
             TokenKind::ASSIGN => f.write_str("="),
+            TokenKind::PLUSEQ => f.write_str("+="),
+            TokenKind::MINUSEQ => f.write_str("-="),
+            TokenKind::ASTERISKEQ => f.write_str("*="),
+            TokenKind::SLASHEQ => f.write_str("/="),
             TokenKind::PLUS => f.write_str("+"),
             TokenKind::MINUS => f.write_str("-"),
             TokenKind::BANG => f.write_str("!"),
             TokenKind::ASTERISK => f.write_str("*"),
             TokenKind::SLASH => f.write_str("/"),
+            TokenKind::PERCENT => f.write_str("%"),
+            TokenKind::POWER => f.write_str("**"),
+            TokenKind::AND => f.write_str("&&"),
+            TokenKind::OR => f.write_str("||"),
+            TokenKind::AMPERSAND => f.write_str("&"),
+            TokenKind::PIPE => f.write_str("|"),
+            TokenKind::CARET => f.write_str("^"),
+            TokenKind::SHL => f.write_str("<<"),
+            TokenKind::SHR => f.write_str(">>"),
             TokenKind::COMMA => f.write_str(","),
             TokenKind::SEMICOLON => f.write_str(";"),
             TokenKind::COLON => f.write_str(":"),
@@ -70,10 +110,19 @@ impl Display for TokenKind {
             TokenKind::IF => f.write_str("if"),
             TokenKind::ELSE => f.write_str("else"),
             TokenKind::RETURN => f.write_str("return"),
+            TokenKind::WHILE => f.write_str("while"),
+            TokenKind::FOR => f.write_str("for"),
+            TokenKind::IN => f.write_str("in"),
+            TokenKind::BREAK => f.write_str("break"),
+            TokenKind::CONTINUE => f.write_str("continue"),
             TokenKind::EQ => f.write_str("=="),
             TokenKind::NEQ => f.write_str("!="),
             TokenKind::BLANK => f.write_str(""),
             TokenKind::STRING => f.write_str("STRING"),
+            TokenKind::STRINGHEAD => f.write_str("STRINGHEAD"),
+            TokenKind::STRINGMID => f.write_str("STRINGMID"),
+            TokenKind::STRINGTAIL => f.write_str("STRINGTAIL"),
+            TokenKind::DOC => f.write_str("///"),
         }
     }
 }